@@ -0,0 +1,655 @@
+use std::{env, path::{Path, PathBuf}, thread, time::{Duration, Instant, SystemTime, UNIX_EPOCH}};
+
+use glfw::{self, Action, Context as _, Glfw, Key, Window, WindowEvent};
+
+use image::DynamicImage;
+#[cfg(feature = "headless")]
+use image::RgbaImage;
+
+#[cfg(feature = "headless")]
+use crate::headless::Headless;
+use crate::capture::{self, PixelFormat, Supersample};
+use crate::clock::{Clock, ClockMode};
+use crate::debug;
+use crate::profiler::{self, Scope};
+use crate::recorder::{RecordFormat, Recorder};
+use crate::ui::DebugUi;
+use crate::uniforms;
+use crate::window::{WindowBuilder, WindowError};
+
+// What the runner hands to every App callback. Chapters go through its
+// methods rather than the window so they also run headless.
+pub struct Context {
+    surface: Surface,
+    // what to animate with, see Settings::clock
+    pub clock: Clock,
+    // simulation time in seconds, advanced by fixed steps
+    pub time: f64,
+    pub frame: u64,
+    screenshot: Option<(PathBuf, u32)>,
+    recording: Option<Recording>,
+    // while rendering into a supersampled capture target
+    render_size: Option<(i32, i32)>
+}
+
+struct Recording {
+    recorder: Recorder,
+    frames_left: Option<u64>,
+    // put back when the recording stops
+    clock: ClockMode
+}
+
+enum Surface {
+    Window { glfw: Glfw, window: Window },
+    #[cfg(feature = "headless")]
+    Headless { headless: Headless, quit: bool }
+}
+
+impl Context {
+    fn new(surface: Surface, clock: ClockMode) -> Context {
+        Context {
+            surface,
+            clock: Clock::new(clock),
+            time: 0.,
+            frame: 0,
+            screenshot: None,
+            recording: None,
+            render_size: None
+        }
+    }
+
+    pub fn quit(&mut self) {
+        match &mut self.surface {
+            Surface::Window { window, .. } => window.set_should_close(true),
+            #[cfg(feature = "headless")]
+            Surface::Headless { quit, .. } => *quit = true
+        }
+    }
+
+    pub fn should_close(&self) -> bool {
+        match &self.surface {
+            Surface::Window { window, .. } => window.should_close(),
+            #[cfg(feature = "headless")]
+            Surface::Headless { quit, .. } => *quit
+        }
+    }
+
+    pub fn is_headless(&self) -> bool {
+        match &self.surface {
+            Surface::Window { .. } => false,
+            #[cfg(feature = "headless")]
+            Surface::Headless { .. } => true
+        }
+    }
+
+    // None when headless, for what only exists with a real window (gamepads).
+    pub fn glfw(&self) -> Option<&Glfw> {
+        match &self.surface {
+            Surface::Window { glfw, .. } => Some(glfw),
+            #[cfg(feature = "headless")]
+            Surface::Headless { .. } => None
+        }
+    }
+
+    // Window size in screen coordinates, which is what cursor positions use.
+    pub fn size(&self) -> (i32, i32) {
+        match &self.surface {
+            Surface::Window { window, .. } => window.get_size(),
+            #[cfg(feature = "headless")]
+            Surface::Headless { headless, .. } => headless.size()
+        }
+    }
+
+    // The size of what is being rendered into, bigger than the window while
+    // a supersampled screenshot is taken.
+    pub fn framebuffer_size(&self) -> (i32, i32) {
+        if let Some(size) = self.render_size {
+            return size;
+        }
+        match &self.surface {
+            Surface::Window { window, .. } => window.get_framebuffer_size(),
+            #[cfg(feature = "headless")]
+            Surface::Headless { headless, .. } => headless.size()
+        }
+    }
+
+    // Headless the cursor sits in the middle of the surface.
+    pub fn cursor_pos(&self) -> (f64, f64) {
+        match &self.surface {
+            Surface::Window { window, .. } => window.get_cursor_pos(),
+            #[cfg(feature = "headless")]
+            Surface::Headless { headless, .. } => {
+                let (width, height) = headless.size();
+                (width as f64 / 2., height as f64 / 2.)
+            }
+        }
+    }
+
+    pub fn set_title(&mut self, title: &str) {
+        match &mut self.surface {
+            Surface::Window { window, .. } => window.set_title(title),
+            #[cfg(feature = "headless")]
+            Surface::Headless { .. } => {}
+        }
+    }
+
+    pub(crate) fn clipboard(&self) -> Option<String> {
+        match &self.surface {
+            Surface::Window { window, .. } => window.get_clipboard_string(),
+            #[cfg(feature = "headless")]
+            Surface::Headless { .. } => None
+        }
+    }
+
+    pub(crate) fn set_clipboard(&mut self, text: &str) {
+        match &mut self.surface {
+            Surface::Window { window, .. } => window.set_clipboard_string(text),
+            #[cfg(feature = "headless")]
+            Surface::Headless { .. } => {}
+        }
+    }
+
+    pub fn set_cursor_mode(&mut self, mode: glfw::CursorMode) {
+        match &mut self.surface {
+            Surface::Window { window, .. } => window.set_cursor_mode(mode),
+            #[cfg(feature = "headless")]
+            Surface::Headless { .. } => {}
+        }
+    }
+
+    // Saves the next rendered frame as a PNG. With a `scale` over 1 that frame
+    // is rendered again offscreen at `scale` times the size and downsampled.
+    pub fn screenshot<P: Into<PathBuf>>(&mut self, path: P, scale: u32) {
+        self.screenshot = Some((path.into(), scale));
+    }
+
+    // Records every frame from the next one on, `seconds` long or until
+    // stop_recording(). The clock runs in fixed 1 / fps steps meanwhile, so
+    // the recording plays at the right speed however slow encoding is.
+    pub fn start_recording<P: Into<PathBuf>>(&mut self, path: P, format: RecordFormat, fps: f64, seconds: Option<f64>) {
+        self.stop_recording();
+        let path = path.into();
+        match Recorder::start(path.clone(), format, fps) {
+            Ok(recorder) => {
+                println!("recording to {}", path.display());
+                let clock = self.clock.mode().clone();
+                self.clock.set_mode(ClockMode::Fixed { step: 1. / fps });
+                self.recording = Some(Recording {
+                    recorder,
+                    frames_left: seconds.map(|seconds| ((seconds * fps).round() as u64).max(1)),
+                    clock
+                });
+            }
+            Err(e) => println!("ERROR::RECORDING {}: {}", path.display(), e)
+        }
+    }
+
+    pub fn stop_recording(&mut self) {
+        if let Some(recording) = self.recording.take() {
+            self.clock.set_mode(recording.clock);
+            let path = recording.recorder.path().to_path_buf();
+            match recording.recorder.finish() {
+                Ok(frames) => println!("saved {} frames to {}", frames, path.display()),
+                Err(e) => println!("ERROR::RECORDING {}: {}", path.display(), e)
+            }
+        }
+    }
+
+    pub fn is_recording(&self) -> bool {
+        self.recording.is_some()
+    }
+
+    fn poll_events(&mut self) {
+        match &mut self.surface {
+            Surface::Window { glfw, .. } => glfw.poll_events(),
+            #[cfg(feature = "headless")]
+            Surface::Headless { .. } => {}
+        }
+    }
+
+    fn swap_buffers(&mut self) {
+        match &mut self.surface {
+            Surface::Window { window, .. } => window.swap_buffers(),
+            // a pbuffer has only the one buffer
+            #[cfg(feature = "headless")]
+            Surface::Headless { .. } => {}
+        }
+    }
+}
+
+pub trait App {
+    // Called once the GL context is current and the functions are loaded.
+    fn init(ctx: &mut Context) -> Self where Self: Sized;
+
+    // Once per frame with the real frame time, after the frame's events.
+    fn update(&mut self, _ctx: &mut Context, _dt: f32) {}
+
+    // Zero or more times per frame with Settings::fixed_step.
+    fn fixed_update(&mut self, _ctx: &mut Context, _step: f32) {}
+
+    // `alpha` is how far the frame is between the last two fixed updates,
+    // from 0 to 1, for interpolating simulated state.
+    fn render(&mut self, ctx: &mut Context, alpha: f32);
+
+    fn on_event(&mut self, _ctx: &mut Context, _event: &WindowEvent) {}
+
+    // The chapter's debug panels, every frame while F2 shows the UI. Build
+    // them with egui::Window and the helpers in common::ui.
+    fn ui(&mut self, _ctx: &mut Context, _ui: &egui::Context) {}
+
+    fn shutdown(&mut self, _ctx: &mut Context) {}
+}
+
+// A chapter as registered with the launcher.
+pub struct Chapter {
+    pub name: &'static str,
+    // the chapter's shaders/ and assets/ paths are relative to this, pass
+    // env!("CARGO_MANIFEST_DIR")
+    pub dir: &'static str,
+    init: fn(&mut Context) -> Box<dyn App>
+}
+
+impl Chapter {
+    pub fn new<A: App + 'static>(name: &'static str, dir: &'static str) -> Chapter {
+        Chapter { name, dir, init: init_boxed::<A> }
+    }
+}
+
+fn init_boxed<A: App + 'static>(ctx: &mut Context) -> Box<dyn App> {
+    Box::new(A::init(ctx))
+}
+
+pub struct Settings {
+    pub window: WindowBuilder,
+    // sleeps to stay under this rate, on top of vsync
+    pub max_fps: Option<f64>,
+    pub fixed_step: f64,
+    // real-time frame times are clamped to this so a long stall (a
+    // breakpoint, a dragged window) runs a bounded number of fixed updates
+    // instead of falling further behind every frame
+    pub max_frame_time: f64,
+    // Also paces the simulation: every frame's update and fixed updates get
+    // the clock's delta, so a fixed or scripted clock makes the whole frame
+    // reproducible.
+    pub clock: ClockMode,
+    // F12 saves screenshots in here, relative to where run() was started
+    pub screenshot_dir: PathBuf,
+    // supersampling of F12 screenshots and headless captures
+    pub screenshot_scale: u32,
+    // F9 starts and stops recordings in here, relative to where run() was
+    // started
+    pub record_dir: PathBuf,
+    pub record_format: RecordFormat,
+    pub record_fps: f64,
+    // records this long from the start when set
+    pub record_seconds: Option<f64>,
+    // profiles every frame and prints the stats when the run ends
+    pub profile: bool,
+    // writes a Chrome trace of the whole run here when set, relative to
+    // where run() was started
+    pub trace: Option<PathBuf>
+}
+
+impl Settings {
+    pub fn new(window: WindowBuilder) -> Settings {
+        Settings {
+            window,
+            max_fps: None,
+            fixed_step: 1. / 60.,
+            max_frame_time: 0.25,
+            clock: ClockMode::RealTime,
+            screenshot_dir: PathBuf::from("screenshots"),
+            screenshot_scale: 1,
+            record_dir: PathBuf::from("recordings"),
+            record_format: RecordFormat::Gif,
+            record_fps: 30.,
+            record_seconds: None,
+            profile: false,
+            trace: None
+        }
+    }
+}
+
+// Runs chapters[start] in one window. F1 opens a menu in the title bar to
+// switch to another chapter without restarting, Left/Right pick one, Enter
+// runs it and F1 or Escape closes the menu. F12 saves a screenshot, F9 starts
+// and stops a recording, F3 shows the profiler's stats in the title bar and F2
+// the chapter's debug UI.
+pub fn run(settings: Settings, chapters: &[Chapter], start: usize) -> Result<(), WindowError> {
+    let (glfw, mut window, events) = settings.window.build()?;
+    window.set_all_polling(true);
+
+    let screenshot_dir = from_start_dir(&settings.screenshot_dir);
+    let record_dir = from_start_dir(&settings.record_dir);
+    let trace = settings.trace.as_ref().map(|path| from_start_dir(path));
+
+    let mut ctx = Context::new(Surface::Window { glfw, window }, settings.clock.clone());
+    let mut current = start;
+    let mut app = start_chapter(&mut ctx, &chapters[current]);
+    let mut menu: Option<usize> = None;
+    // when the profiler overlay last updated the title, None while it's off
+    let mut overlay: Option<Instant> = None;
+    start_profiling(&settings);
+    if let Some(seconds) = settings.record_seconds {
+        let path = record_dir.join(capture_name(&chapters[current], &ctx, &settings.record_format.extension()));
+        ctx.start_recording(path, settings.record_format.clone(), settings.record_fps, Some(seconds));
+    }
+    let mut ui = DebugUi::new();
+
+    let mut accumulator = 0.;
+    while !ctx.should_close() {
+        let frame_start = Instant::now();
+        ctx.poll_events();
+        let mut switch_to = None;
+        for (_, event) in glfw::flush_messages(&events) {
+            // the UI gets first pick, what it uses the chapter doesn't see
+            if menu.is_none() && ui.visible && ui.on_event(&ctx, &event) {
+                continue;
+            }
+            match (menu, &event) {
+                (None, WindowEvent::Key(Key::F1, _, Action::Press, _)) => {
+                    menu = Some(current);
+                    show_menu(&mut ctx, chapters, current);
+                }
+                (None, WindowEvent::Key(Key::F12, _, Action::Press, _)) => {
+                    let path = screenshot_dir.join(capture_name(&chapters[current], &ctx, ".png"));
+                    ctx.screenshot(path, settings.screenshot_scale);
+                }
+                (None, WindowEvent::Key(Key::F3, _, Action::Press, _)) => {
+                    if overlay.take().is_some() {
+                        profiler::set_enabled(settings.profile || trace.is_some());
+                        ctx.set_title(chapters[current].name);
+                    } else {
+                        profiler::set_enabled(true);
+                        overlay = Some(Instant::now());
+                    }
+                }
+                (None, WindowEvent::Key(Key::F2, _, Action::Press, _)) => ui.visible = !ui.visible,
+                (None, WindowEvent::Key(Key::F9, _, Action::Press, _)) => {
+                    if ctx.is_recording() {
+                        ctx.stop_recording();
+                    } else {
+                        let path = record_dir.join(capture_name(&chapters[current], &ctx, &settings.record_format.extension()));
+                        ctx.start_recording(path, settings.record_format.clone(), settings.record_fps, None);
+                    }
+                }
+                (None, _) => app.on_event(&mut ctx, &event),
+                (Some(selected), WindowEvent::Key(key, _, Action::Press, _))
+                | (Some(selected), WindowEvent::Key(key, _, Action::Repeat, _)) => match key {
+                    Key::Left | Key::Up => {
+                        let selected = (selected + chapters.len() - 1) % chapters.len();
+                        menu = Some(selected);
+                        show_menu(&mut ctx, chapters, selected);
+                    }
+                    Key::Right | Key::Down => {
+                        let selected = (selected + 1) % chapters.len();
+                        menu = Some(selected);
+                        show_menu(&mut ctx, chapters, selected);
+                    }
+                    Key::Enter => {
+                        menu = None;
+                        switch_to = Some(selected);
+                    }
+                    Key::F1 | Key::Escape => {
+                        menu = None;
+                        ctx.set_title(chapters[current].name);
+                    }
+                    _ => {}
+                },
+                // the chapter still has to follow resizes while the menu is open
+                (Some(_), WindowEvent::FramebufferSize(..)) => app.on_event(&mut ctx, &event),
+                (Some(_), _) => {}
+            }
+        }
+        if let Some(next) = switch_to {
+            ctx.stop_recording();
+            app.shutdown(&mut ctx);
+            current = next;
+            app = start_chapter(&mut ctx, &chapters[current]);
+            accumulator = 0.;
+            continue;
+        }
+
+        frame(&mut ctx, &mut *app, &settings, &mut accumulator);
+        // after frame() so screenshots and recordings leave it out
+        if ui.visible {
+            crate::profile!("debug ui", { ui.frame(&mut ctx, &mut *app) });
+        }
+        ctx.swap_buffers();
+
+        if let Some(shown) = overlay {
+            if menu.is_none() && shown.elapsed().as_secs_f64() >= OVERLAY_INTERVAL {
+                ctx.set_title(&overlay_title(&chapters[current]));
+                overlay = Some(Instant::now());
+            }
+        }
+
+        if let Some(max_fps) = settings.max_fps {
+            if let Some(remaining) = Duration::from_secs_f64(1. / max_fps).checked_sub(frame_start.elapsed()) {
+                thread::sleep(remaining);
+            }
+        }
+    }
+    ctx.stop_recording();
+    app.shutdown(&mut ctx);
+    drop(ui);
+    finish_profiling(&settings, trace.as_deref());
+    Ok(())
+}
+
+// seconds between updates of the profiler overlay, so it can be read
+const OVERLAY_INTERVAL: f64 = 0.5;
+
+fn start_profiling(settings: &Settings) {
+    profiler::set_enabled(settings.profile || settings.trace.is_some());
+    if settings.trace.is_some() {
+        profiler::start_trace();
+    }
+}
+
+// Writes the trace and prints the stats asked for, then lets go of the
+// profiler's queries before the context does.
+fn finish_profiling(settings: &Settings, trace: Option<&Path>) {
+    if let Some(path) = trace {
+        match profiler::write_trace(path) {
+            Ok(events) => println!("saved {} trace events to {}", events, path.display()),
+            Err(e) => println!("ERROR::TRACE {}: {}", path.display(), e)
+        }
+    }
+    if settings.profile {
+        profiler::flush();
+        print!("{}", profiler::report());
+    }
+    profiler::release();
+    profiler::set_enabled(false);
+}
+
+// Average and worst CPU (and GPU) milliseconds of every scope, nested ones
+// included.
+fn overlay_title(chapter: &Chapter) -> String {
+    let scopes: Vec<String> = profiler::stats()
+        .iter()
+        .map(|stats| match stats.gpu {
+            Some(gpu) => format!("{} {:.2}/{:.2} gpu {:.2}/{:.2}", stats.name, stats.cpu.avg, stats.cpu.max, gpu.avg, gpu.max),
+            None => format!("{} {:.2}/{:.2}", stats.name, stats.cpu.avg, stats.cpu.max)
+        })
+        .collect();
+    format!("{} | ms avg/max: {}", chapter.name, scopes.join(", "))
+}
+
+// Chapters run from their own directories, output paths are taken relative
+// to where the launcher was started instead. Call it before a chapter starts.
+pub fn from_start_dir(path: &Path) -> PathBuf {
+    env::current_dir().map(|dir| dir.join(path)).unwrap_or_else(|_| path.to_path_buf())
+}
+
+// <chapter>-<unix time>-<frame><extension>, unique across runs
+fn capture_name(chapter: &Chapter, ctx: &Context, extension: &str) -> String {
+    let seconds = SystemTime::now().duration_since(UNIX_EPOCH).map_or(0, |d| d.as_secs());
+    format!("{}-{}-{}{}", chapter.name, seconds, ctx.frame, extension)
+}
+
+const STEP_TOLERANCE: f64 = 1e-9;
+
+// Advances the clock and runs one frame's update, fixed updates and render,
+// then takes the screenshot if one was asked for and records the frame.
+// Returns the render's alpha.
+fn frame(ctx: &mut Context, app: &mut dyn App, settings: &Settings, accumulator: &mut f64) -> f32 {
+    profiler::begin_frame();
+    let _frame = Scope::begin("frame");
+    ctx.clock.advance();
+    let frame_time = if ctx.clock.is_real_time() {
+        ctx.clock.delta().min(settings.max_frame_time)
+    } else {
+        ctx.clock.delta()
+    };
+
+    crate::profile!("update", { app.update(ctx, frame_time as f32) });
+
+    *accumulator += frame_time;
+    // without the tolerance a second of 1/60 steps adds up to only 59 of
+    // them, and a clock at t = 1 would render the simulation a step early
+    while *accumulator >= settings.fixed_step - STEP_TOLERANCE {
+        crate::profile!("fixed update", { app.fixed_update(ctx, settings.fixed_step as f32) });
+        ctx.time += settings.fixed_step;
+        *accumulator -= settings.fixed_step;
+    }
+
+    let alpha = (accumulator.max(0.) / settings.fixed_step) as f32;
+    crate::profile!("render", { app.render(ctx, alpha) });
+    ctx.frame += 1;
+
+    if let Some((path, scale)) = ctx.screenshot.take() {
+        let saved = capture_frame(ctx, app, alpha, scale, settings.window.srgb, PixelFormat::Rgb)
+            .and_then(|image| capture::save_png(&image, &path));
+        match saved {
+            Ok(()) => println!("saved {}", path.display()),
+            Err(e) => println!("ERROR::SCREENSHOT {}: {}", path.display(), e)
+        }
+    }
+
+    let (width, height) = ctx.framebuffer_size();
+    if let Some(recording) = &mut ctx.recording {
+        // a minimized window has nothing to record
+        let pushed = match capture::capture_framebuffer(0, width, height, PixelFormat::Rgb) {
+            Ok(image) => recording.recorder.push(image.to_rgb()),
+            Err(_) => Ok(())
+        };
+        if let Err(e) = pushed {
+            println!("ERROR::RECORDING the window was resized, stopping: {}", e);
+            ctx.stop_recording();
+        } else if let Some(frames_left) = &mut recording.frames_left {
+            *frames_left -= 1;
+            if *frames_left == 0 {
+                ctx.stop_recording();
+            }
+        }
+    }
+    // panics with the frame's first GL error when asked to
+    debug::check();
+    alpha
+}
+
+// The frame just rendered. Supersampled captures render it again into an
+// offscreen target, telling the app about the bigger framebuffer for the
+// duration.
+fn capture_frame(ctx: &mut Context, app: &mut dyn App, alpha: f32, scale: u32, srgb: bool, format: PixelFormat) -> Result<DynamicImage, String> {
+    let (width, height) = ctx.framebuffer_size();
+    // a minimized window's empty framebuffer fails in capture_framebuffer
+    if scale <= 1 || width <= 0 || height <= 0 {
+        return capture::capture_framebuffer(0, width, height, format);
+    }
+
+    let target = Supersample::new(width, height, scale, srgb)?;
+    ctx.render_size = Some((target.width, target.height));
+    app.on_event(ctx, &WindowEvent::FramebufferSize(target.width, target.height));
+    // after the event, resizing may have bound other framebuffers
+    target.bind();
+    app.render(ctx, alpha);
+    let image = target.capture(PixelFormat::Rgba);
+
+    ctx.render_size = None;
+    unsafe {
+        gl::BindFramebuffer(gl::FRAMEBUFFER, 0);
+        gl::Viewport(0, 0, width, height);
+    }
+    app.on_event(ctx, &WindowEvent::FramebufferSize(width, height));
+
+    let image = DynamicImage::ImageRgba8(capture::downsample(&image?.to_rgba(), scale));
+    Ok(match format {
+        PixelFormat::Rgb => DynamicImage::ImageRgb8(image.to_rgb()),
+        PixelFormat::Rgba => image
+    })
+}
+
+// Renders `frames` frames of one chapter offscreen, or more until a
+// Settings::record_seconds recording is done, and returns the last one
+// supersampled by Settings::screenshot_scale. With a fixed or scripted
+// Settings::clock the result only depends on the chapter and the frame
+// count, not on how fast the machine is.
+#[cfg(feature = "headless")]
+pub fn run_headless(settings: &Settings, chapter: &Chapter, frames: u64) -> Result<RgbaImage, WindowError> {
+    let headless = Headless::new(&settings.window)?;
+    let record_dir = from_start_dir(&settings.record_dir);
+    let trace = settings.trace.as_ref().map(|path| from_start_dir(path));
+    let mut ctx = Context::new(Surface::Headless { headless, quit: false }, settings.clock.clone());
+    let mut app = start_chapter(&mut ctx, chapter);
+    if let Some(seconds) = settings.record_seconds {
+        let path = record_dir.join(capture_name(chapter, &ctx, &settings.record_format.extension()));
+        ctx.start_recording(path, settings.record_format.clone(), settings.record_fps, Some(seconds));
+    }
+    start_profiling(settings);
+
+    let (mut accumulator, mut alpha) = (0., 0.);
+    while (ctx.frame < frames || ctx.is_recording()) && !ctx.should_close() {
+        alpha = frame(&mut ctx, &mut *app, settings, &mut accumulator);
+    }
+    ctx.stop_recording();
+    let image = capture_frame(&mut ctx, &mut *app, alpha, settings.screenshot_scale, settings.window.srgb, PixelFormat::Rgba)
+        .map_err(WindowError::Headless)?;
+    app.shutdown(&mut ctx);
+    finish_profiling(settings, trace.as_deref());
+    Ok(image.to_rgba())
+}
+
+// Puts back the state chapters assume they start with, then inits the chapter
+// from its own directory.
+fn start_chapter(ctx: &mut Context, chapter: &Chapter) -> Box<dyn App> {
+    if let Err(e) = env::set_current_dir(chapter.dir) {
+        println!("ERROR::CHAPTER::DIR {}: {}", chapter.dir, e);
+    }
+    ctx.set_title(chapter.name);
+    ctx.set_cursor_mode(glfw::CursorMode::Normal);
+    ctx.time = 0.;
+    // the inspector lists the new chapter's programs only
+    uniforms::reset();
+    let (width, height) = ctx.framebuffer_size();
+    unsafe {
+        gl::BindFramebuffer(gl::FRAMEBUFFER, 0);
+        gl::Viewport(0, 0, width, height);
+        gl::UseProgram(0);
+        gl::BindVertexArray(0);
+        gl::Disable(gl::DEPTH_TEST);
+        gl::DepthFunc(gl::LESS);
+        gl::ClearDepth(1.0);
+        if gl::ClipControl::is_loaded() {
+            gl::ClipControl(gl::LOWER_LEFT, gl::NEGATIVE_ONE_TO_ONE);
+        }
+        gl::Disable(gl::FRAMEBUFFER_SRGB);
+        gl::PolygonMode(gl::FRONT_AND_BACK, gl::FILL);
+    }
+    let app = (chapter.init)(ctx);
+    // loading is not part of the first frame
+    ctx.clock.reset();
+    profiler::reset();
+    app
+}
+
+fn show_menu(ctx: &mut Context, chapters: &[Chapter], selected: usize) {
+    let names: Vec<String> = chapters
+        .iter()
+        .enumerate()
+        .map(|(i, c)| if i == selected { format!("[{}]", c.name) } else { c.name.to_string() })
+        .collect();
+    ctx.set_title(&format!("{}  (Enter to run, F1 to close)", names.join(" ")));
+}
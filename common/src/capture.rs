@@ -0,0 +1,191 @@
+// Reading rendered frames back into images, for screenshots, recordings and
+// the golden checks.
+use std::{convert::TryFrom, os::raw::c_void, path::Path};
+
+use gl::types::{GLint, GLuint};
+use image::{DynamicImage, Pixel, Rgba, RgbImage, RgbaImage};
+
+use crate::debug;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum PixelFormat {
+    Rgb,
+    Rgba
+}
+
+// Reads the color of `framebuffer` (0 for the default one, its back buffer)
+// top row first. The read framebuffer binding and pack alignment are put back
+// afterwards. Fails on an empty framebuffer, e.g. of a minimized window.
+pub fn capture_framebuffer(framebuffer: GLuint, width: i32, height: i32, format: PixelFormat) -> Result<DynamicImage, String> {
+    if width <= 0 || height <= 0 {
+        return Err(format!("nothing to capture in a {}x{} framebuffer", width, height));
+    }
+    let (gl_format, channels) = match format {
+        PixelFormat::Rgb => (gl::RGB, 3),
+        PixelFormat::Rgba => (gl::RGBA, 4)
+    };
+    let row = (width * channels) as usize;
+    let mut pixels = vec![0u8; row * height as usize];
+    unsafe {
+        let (mut previous_framebuffer, mut previous_alignment): (GLint, GLint) = (0, 0);
+        gl::GetIntegerv(gl::READ_FRAMEBUFFER_BINDING, &mut previous_framebuffer);
+        gl::GetIntegerv(gl::PACK_ALIGNMENT, &mut previous_alignment);
+
+        gl::BindFramebuffer(gl::READ_FRAMEBUFFER, framebuffer);
+        // rows are tightly packed below, RGB rows of most widths are not a
+        // multiple of the default alignment of 4
+        gl::PixelStorei(gl::PACK_ALIGNMENT, 1);
+        gl::ReadPixels(0, 0, width, height, gl_format, gl::UNSIGNED_BYTE, pixels.as_mut_ptr() as *mut c_void);
+
+        gl::PixelStorei(gl::PACK_ALIGNMENT, previous_alignment);
+        gl::BindFramebuffer(gl::READ_FRAMEBUFFER, previous_framebuffer as GLuint);
+    }
+
+    // GL's rows start at the bottom
+    let flipped: Vec<u8> = pixels.chunks(row).rev().flatten().cloned().collect();
+    let (width, height) = (width as u32, height as u32);
+    Ok(match format {
+        PixelFormat::Rgb => DynamicImage::ImageRgb8(RgbImage::from_raw(width, height, flipped).unwrap()),
+        PixelFormat::Rgba => DynamicImage::ImageRgba8(RgbaImage::from_raw(width, height, flipped).unwrap())
+    })
+}
+
+pub fn save_png<P: AsRef<Path>>(image: &DynamicImage, path: P) -> Result<(), String> {
+    let path = path.as_ref();
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir).map_err(|e| e.to_string())?;
+    }
+    image.save(path).map_err(|e| e.to_string())
+}
+
+// Offscreen color and depth/stencil target `scale` times a framebuffer's
+// size. Render into it, read it back and downsample() for an anti-aliased
+// capture at the original size.
+pub struct Supersample {
+    fbo: GLuint,
+    color: GLuint,
+    depth_stencil: GLuint,
+    pub width: i32,
+    pub height: i32,
+    pub scale: u32
+}
+
+impl Supersample {
+    // `srgb` gives the color buffer the same FRAMEBUFFER_SRGB behavior as an
+    // sRGB capable default framebuffer.
+    pub fn new(width: i32, height: i32, scale: u32, srgb: bool) -> Result<Supersample, String> {
+        let mut max_size = 0;
+        unsafe {
+            gl::GetIntegerv(gl::MAX_RENDERBUFFER_SIZE, &mut max_size);
+        }
+        let (width, height) = scaled_size(width, height, scale, max_size)?;
+
+        let (mut fbo, mut color, mut depth_stencil) = (0, 0, 0);
+        unsafe {
+            gl::GenFramebuffers(1, &mut fbo);
+            gl::BindFramebuffer(gl::FRAMEBUFFER, fbo);
+
+            gl::GenRenderbuffers(1, &mut color);
+            gl::BindRenderbuffer(gl::RENDERBUFFER, color);
+            gl::RenderbufferStorage(gl::RENDERBUFFER, if srgb { gl::SRGB8_ALPHA8 } else { gl::RGBA8 }, width, height);
+            gl::FramebufferRenderbuffer(gl::FRAMEBUFFER, gl::COLOR_ATTACHMENT0, gl::RENDERBUFFER, color);
+
+            gl::GenRenderbuffers(1, &mut depth_stencil);
+            gl::BindRenderbuffer(gl::RENDERBUFFER, depth_stencil);
+            gl::RenderbufferStorage(gl::RENDERBUFFER, gl::DEPTH24_STENCIL8, width, height);
+            gl::FramebufferRenderbuffer(gl::FRAMEBUFFER, gl::DEPTH_STENCIL_ATTACHMENT, gl::RENDERBUFFER, depth_stencil);
+
+            gl::BindRenderbuffer(gl::RENDERBUFFER, 0);
+            debug::label(gl::FRAMEBUFFER, fbo, "supersample");
+            debug::label(gl::RENDERBUFFER, color, "supersample color");
+            debug::label(gl::RENDERBUFFER, depth_stencil, "supersample depth/stencil");
+            let status = gl::CheckFramebufferStatus(gl::FRAMEBUFFER);
+            gl::BindFramebuffer(gl::FRAMEBUFFER, 0);
+            let target = Supersample { fbo, color, depth_stencil, width, height, scale };
+            if status != gl::FRAMEBUFFER_COMPLETE {
+                return Err(format!("framebuffer incomplete: {:#x}", status));
+            }
+            Ok(target)
+        }
+    }
+
+    pub fn bind(&self) {
+        unsafe {
+            gl::BindFramebuffer(gl::FRAMEBUFFER, self.fbo);
+            gl::Viewport(0, 0, self.width, self.height);
+        }
+    }
+
+    pub fn capture(&self, format: PixelFormat) -> Result<DynamicImage, String> {
+        capture_framebuffer(self.fbo, self.width, self.height, format)
+    }
+}
+
+impl Drop for Supersample {
+    fn drop(&mut self) {
+        unsafe {
+            gl::DeleteFramebuffers(1, &self.fbo);
+            gl::DeleteRenderbuffers(1, &self.color);
+            gl::DeleteRenderbuffers(1, &self.depth_stencil);
+        }
+    }
+}
+
+// `width` x `height` times `scale`, as long as it fits in a renderbuffer of
+// at most `max_size` pixels on a side.
+fn scaled_size(width: i32, height: i32, scale: u32, max_size: i32) -> Result<(i32, i32), String> {
+    let too_big = || format!("{}x{} at {}x is over the renderbuffer limit of {}", width, height, scale, max_size);
+    let scale = i32::try_from(scale).map_err(|_| too_big())?;
+    match (width.checked_mul(scale), height.checked_mul(scale)) {
+        (Some(w), Some(h)) if w <= max_size && h <= max_size => Ok((w, h)),
+        _ => Err(too_big())
+    }
+}
+
+// Averages every scale x scale block into one pixel.
+pub fn downsample(image: &RgbaImage, scale: u32) -> RgbaImage {
+    let (width, height) = (image.width() / scale, image.height() / scale);
+    let samples = scale * scale;
+    RgbaImage::from_fn(width, height, |x, y| {
+        let mut sum = [0u32; 4];
+        for sy in 0..scale {
+            for sx in 0..scale {
+                let pixel = image.get_pixel(x * scale + sx, y * scale + sy);
+                for (total, value) in sum.iter_mut().zip(pixel.channels()) {
+                    *total += *value as u32;
+                }
+            }
+        }
+        Rgba::from_channels(
+            ((sum[0] + samples / 2) / samples) as u8,
+            ((sum[1] + samples / 2) / samples) as u8,
+            ((sum[2] + samples / 2) / samples) as u8,
+            ((sum[3] + samples / 2) / samples) as u8
+        )
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn scaled_size_fits() {
+        assert_eq!(scaled_size(800, 600, 4, 16384), Ok((3200, 2400)));
+        assert_eq!(scaled_size(4096, 100, 4, 16384), Ok((16384, 400)));
+    }
+
+    #[test]
+    fn scaled_size_over_the_limit() {
+        assert_eq!(
+            scaled_size(4097, 100, 4, 16384),
+            Err("4097x100 at 4x is over the renderbuffer limit of 16384".to_string())
+        );
+    }
+
+    #[test]
+    fn scaled_size_overflow() {
+        assert!(scaled_size(1 << 20, 600, 1 << 12, i32::MAX).is_err());
+        assert!(scaled_size(800, 600, u32::MAX, i32::MAX).is_err());
+    }
+}
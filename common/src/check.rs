@@ -0,0 +1,58 @@
+// glGetError after every wrapped call, for contexts without debug output
+// (macOS stops at 4.1, KHR_debug needs 4.3). Wrap calls in gl_check! and
+// build with the gl-check feature:
+//
+//     unsafe { gl_check!(gl::TexImage2D(...)); }
+//
+// Without the feature the macro is just the call. Errors are logged under
+// the "gl" target and count for debug::check() like high severity messages.
+use gl::types::GLenum;
+
+// Evaluates a GL call and, with the gl-check feature, reports every error
+// flag it left with the call's source and location. Errors an earlier
+// unwrapped call left behind are blamed on this one.
+#[macro_export]
+macro_rules! gl_check {
+    ($call:expr) => {{
+        let result = $call;
+        $crate::check::after_call(stringify!($call), file!(), line!());
+        result
+    }};
+}
+
+#[cfg(feature = "gl-check")]
+#[doc(hidden)]
+pub fn after_call(call: &str, file: &str, line: u32) {
+    // glGetError only returns NO_ERROR once every flag is cleared, but a lost
+    // context may never get there
+    const MAX_ERRORS: usize = 8;
+    for _ in 0..MAX_ERRORS {
+        let error = unsafe { gl::GetError() };
+        if error == gl::NO_ERROR {
+            break;
+        }
+        let message = format!("{} in {} at {}:{}", error_name(error), call, file, line);
+        log::error!(target: "gl", "{}", message);
+        crate::debug::record_high(message);
+    }
+}
+
+#[cfg(not(feature = "gl-check"))]
+#[doc(hidden)]
+#[inline(always)]
+pub fn after_call(_call: &str, _file: &str, _line: u32) {}
+
+pub fn error_name(error: GLenum) -> String {
+    match error {
+        gl::NO_ERROR => "GL_NO_ERROR".to_string(),
+        gl::INVALID_ENUM => "GL_INVALID_ENUM".to_string(),
+        gl::INVALID_VALUE => "GL_INVALID_VALUE".to_string(),
+        gl::INVALID_OPERATION => "GL_INVALID_OPERATION".to_string(),
+        gl::INVALID_FRAMEBUFFER_OPERATION => "GL_INVALID_FRAMEBUFFER_OPERATION".to_string(),
+        gl::OUT_OF_MEMORY => "GL_OUT_OF_MEMORY".to_string(),
+        gl::STACK_UNDERFLOW => "GL_STACK_UNDERFLOW".to_string(),
+        gl::STACK_OVERFLOW => "GL_STACK_OVERFLOW".to_string(),
+        gl::CONTEXT_LOST => "GL_CONTEXT_LOST".to_string(),
+        other => format!("unknown GL error {:#x}", other)
+    }
+}
@@ -0,0 +1,82 @@
+use std::time::{Duration, Instant};
+
+// Where the time chapters animate with comes from. Everything but RealTime
+// gives the same frames on every run.
+#[derive(Clone, Debug, PartialEq)]
+pub enum ClockMode {
+    // wall-clock seconds since the chapter started
+    RealTime,
+    // every frame is step after the previous one however long the frames
+    // take, for captures and recordings
+    Fixed { step: f64 },
+    // frame n (counted from when the mode was set) is at times[n], the last
+    // one repeats once they run out
+    Scripted(Vec<f64>)
+}
+
+pub struct Clock {
+    mode: ClockMode,
+    start: Instant,
+    // frames advanced since the last reset
+    frame: u64,
+    // frame the mode took over at, scripts count from there
+    base_frame: u64,
+    time: f64,
+    delta: f64
+}
+
+impl Clock {
+    pub fn new(mode: ClockMode) -> Clock {
+        Clock { mode, start: Instant::now(), frame: 0, base_frame: 0, time: 0., delta: 0. }
+    }
+
+    pub fn mode(&self) -> &ClockMode {
+        &self.mode
+    }
+
+    pub fn is_real_time(&self) -> bool {
+        self.mode == ClockMode::RealTime
+    }
+
+    // Seconds at the current frame, what animations should be driven by.
+    pub fn time(&self) -> f64 {
+        self.time
+    }
+
+    // Seconds since the previous frame, never negative even when a script
+    // goes back in time.
+    pub fn delta(&self) -> f64 {
+        self.delta
+    }
+
+    // Carries on from the current time in another mode, the recorder
+    // switches to fixed steps while it runs.
+    pub(crate) fn set_mode(&mut self, mode: ClockMode) {
+        self.start = Instant::now() - Duration::from_secs_f64(self.time);
+        self.base_frame = self.frame;
+        self.mode = mode;
+    }
+
+    // Back to t = 0 for a fresh chapter.
+    pub(crate) fn reset(&mut self) {
+        *self = Clock::new(self.mode.clone());
+    }
+
+    // Moves to the next frame, called by the runner before the frame's update.
+    pub(crate) fn advance(&mut self) {
+        let time = match &self.mode {
+            ClockMode::RealTime => self.start.elapsed().as_secs_f64(),
+            // the first frame is at 0
+            ClockMode::Fixed { .. } if self.frame == 0 => 0.,
+            ClockMode::Fixed { step } => self.time + step,
+            ClockMode::Scripted(times) => times
+                .get((self.frame - self.base_frame) as usize)
+                .or_else(|| times.last())
+                .cloned()
+                .unwrap_or(self.time)
+        };
+        self.delta = (time - self.time).max(0.);
+        self.time = time;
+        self.frame += 1;
+    }
+}
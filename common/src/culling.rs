@@ -0,0 +1,270 @@
+use cgmath::{Matrix4, Point3, Vector3, Vector4, vec3};
+use cgmath::prelude::*;
+
+use crate::scene::{NodeId, Scene};
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Aabb {
+    pub min: Point3<f32>,
+    pub max: Point3<f32>
+}
+
+impl Aabb {
+    // `stride` is in floats, the position has to be the first three of them
+    pub fn from_vertices(vertices: &[f32], stride: usize) -> Aabb {
+        let mut min = Point3::new(f32::MAX, f32::MAX, f32::MAX);
+        let mut max = Point3::new(f32::MIN, f32::MIN, f32::MIN);
+        for v in vertices.chunks(stride) {
+            min = Point3::new(min.x.min(v[0]), min.y.min(v[1]), min.z.min(v[2]));
+            max = Point3::new(max.x.max(v[0]), max.y.max(v[1]), max.z.max(v[2]));
+        }
+        Aabb { min, max }
+    }
+
+    pub fn center(&self) -> Point3<f32> {
+        self.min.midpoint(self.max)
+    }
+
+    pub fn extents(&self) -> Vector3<f32> {
+        (self.max - self.min) / 2.
+    }
+
+    pub fn corners(&self) -> [Point3<f32>; 8] {
+        let (a, b) = (self.min, self.max);
+        [
+            Point3::new(a.x, a.y, a.z), Point3::new(b.x, a.y, a.z),
+            Point3::new(a.x, b.y, a.z), Point3::new(b.x, b.y, a.z),
+            Point3::new(a.x, a.y, b.z), Point3::new(b.x, a.y, b.z),
+            Point3::new(a.x, b.y, b.z), Point3::new(b.x, b.y, b.z)
+        ]
+    }
+
+    // Box around the transformed corners, still axis aligned so it grows
+    // under rotation.
+    pub fn transform(&self, m: &Matrix4<f32>) -> Aabb {
+        let center = m.transform_point(self.center());
+        let e = self.extents();
+        let extents = vec3(
+            m.x.x.abs() * e.x + m.y.x.abs() * e.y + m.z.x.abs() * e.z,
+            m.x.y.abs() * e.x + m.y.y.abs() * e.y + m.z.y.abs() * e.z,
+            m.x.z.abs() * e.x + m.y.z.abs() * e.y + m.z.z.abs() * e.z
+        );
+        Aabb { min: center - extents, max: center + extents }
+    }
+
+    pub fn bounding_sphere(&self) -> BoundingSphere {
+        BoundingSphere { center: self.center(), radius: self.extents().magnitude() }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct BoundingSphere {
+    pub center: Point3<f32>,
+    pub radius: f32
+}
+
+impl BoundingSphere {
+    pub fn transform(&self, m: &Matrix4<f32>) -> BoundingSphere {
+        let scale = m.x.truncate().magnitude()
+            .max(m.y.truncate().magnitude())
+            .max(m.z.truncate().magnitude());
+        BoundingSphere { center: m.transform_point(self.center), radius: self.radius * scale }
+    }
+}
+
+// Points with a negative distance are behind the plane.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Plane {
+    pub normal: Vector3<f32>,
+    pub d: f32
+}
+
+impl Plane {
+    fn from_row(v: Vector4<f32>) -> Plane {
+        let normal = v.truncate();
+        let len = normal.magnitude();
+        if len <= f32::EPSILON {
+            // e.g. the far plane of an infinite projection, lets everything through
+            return Plane { normal: vec3(0., 0., 0.), d: 1. };
+        }
+        Plane { normal: normal / len, d: v.w / len }
+    }
+
+    pub fn distance(&self, p: Point3<f32>) -> f32 {
+        self.normal.dot(p.to_vec()) + self.d
+    }
+}
+
+pub struct Frustum {
+    // left, right, bottom, top, near, far
+    pub planes: [Plane; 6]
+}
+
+impl Frustum {
+    // Gribb-Hartmann extraction for -w <= z <= w clip space. With a 0..1 depth
+    // range (reversed-Z) the near test just ends up a bit looser.
+    pub fn from_matrix(m: &Matrix4<f32>) -> Frustum {
+        let (r0, r1, r2, r3) = (m.row(0), m.row(1), m.row(2), m.row(3));
+        Frustum {
+            planes: [
+                Plane::from_row(r3 + r0),
+                Plane::from_row(r3 - r0),
+                Plane::from_row(r3 + r1),
+                Plane::from_row(r3 - r1),
+                Plane::from_row(r3 + r2),
+                Plane::from_row(r3 - r2)
+            ]
+        }
+    }
+
+    pub fn contains_point(&self, p: Point3<f32>) -> bool {
+        self.planes.iter().all(|plane| plane.distance(p) >= 0.)
+    }
+
+    pub fn intersects_sphere(&self, sphere: &BoundingSphere) -> bool {
+        self.planes.iter().all(|plane| plane.distance(sphere.center) >= -sphere.radius)
+    }
+
+    // Conservative: boxes near the frustum corners may pass without being visible.
+    pub fn intersects_aabb(&self, aabb: &Aabb) -> bool {
+        self.planes.iter().all(|plane| {
+            let positive = Point3::new(
+                if plane.normal.x >= 0. { aabb.max.x } else { aabb.min.x },
+                if plane.normal.y >= 0. { aabb.max.y } else { aabb.min.y },
+                if plane.normal.z >= 0. { aabb.max.z } else { aabb.min.z }
+            );
+            plane.distance(positive) >= 0.
+        })
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct CullStats {
+    pub visible: usize,
+    pub culled: usize
+}
+
+// Fills `visible` with the nodes that have a mesh touching the frustum. Nodes
+// without bounds are always drawn. World matrices have to be up to date.
+pub fn cull(scene: &Scene, frustum: &Frustum, visible: &mut Vec<NodeId>) -> CullStats {
+    visible.clear();
+    let mut stats = CullStats::default();
+    for (id, node) in scene.nodes() {
+        let mesh = match node.mesh {
+            Some(mesh) => mesh,
+            None => continue
+        };
+        let inside = match mesh.bounds {
+            Some(bounds) => {
+                let world = node.world();
+                frustum.intersects_sphere(&bounds.bounding_sphere().transform(world))
+                    && frustum.intersects_aabb(&bounds.transform(world))
+            }
+            None => true
+        };
+        if inside {
+            visible.push(id);
+            stats.visible += 1;
+        } else {
+            stats.culled += 1;
+        }
+    }
+    stats
+}
+
+#[cfg(test)]
+mod tests {
+    use cgmath::{Deg, Point3, perspective};
+
+    use super::*;
+    use crate::scene::{Mesh, Transform};
+
+    // camera at the origin looking down -z, the side planes are x = ±z and y = ±z
+    fn frustum() -> Frustum {
+        Frustum::from_matrix(&perspective(Deg(90.), 1., 0.1, 100.))
+    }
+
+    fn unit_cube() -> Aabb {
+        Aabb { min: Point3::new(-0.5, -0.5, -0.5), max: Point3::new(0.5, 0.5, 0.5) }
+    }
+
+    fn sphere(x: f32, y: f32, z: f32, radius: f32) -> BoundingSphere {
+        BoundingSphere { center: Point3::new(x, y, z), radius }
+    }
+
+    #[test]
+    fn planes_are_normalized() {
+        for plane in &frustum().planes {
+            assert!((plane.normal.magnitude() - 1.).abs() < 1e-5, "{:?}", plane);
+        }
+        assert!(frustum().planes[4].distance(Point3::new(0., 0., -0.1)).abs() < 1e-4);
+        assert!((frustum().planes[5].distance(Point3::new(0., 0., -90.)) - 10.).abs() < 1e-3);
+    }
+
+    #[test]
+    fn spheres() {
+        let frustum = frustum();
+        // inside
+        assert!(frustum.intersects_sphere(&sphere(0., 0., -10., 1.)));
+        assert!(frustum.intersects_sphere(&sphere(9., -9., -10., 0.5)));
+        // outside: behind, beyond far, left of and above the frustum
+        assert!(!frustum.intersects_sphere(&sphere(0., 0., 10., 1.)));
+        assert!(!frustum.intersects_sphere(&sphere(0., 0., -200., 1.)));
+        assert!(!frustum.intersects_sphere(&sphere(-30., 0., -10., 1.)));
+        assert!(!frustum.intersects_sphere(&sphere(0., 12., -10., 1.)));
+        // straddling the left plane with the center outside, and the near plane
+        assert!(frustum.intersects_sphere(&sphere(-10.5, 0., -10., 1.)));
+        assert!(frustum.intersects_sphere(&sphere(0., 0., 0., 0.5)));
+    }
+
+    #[test]
+    fn transformed_spheres() {
+        let m = Matrix4::from_translation(vec3(-12., 0., -10.)) * Matrix4::from_scale(3.);
+        let s = unit_cube().bounding_sphere().transform(&m);
+        assert_eq!(s.center, Point3::new(-12., 0., -10.));
+        assert!((s.radius - 3. * 0.75f32.sqrt()).abs() < 1e-5);
+        // 2 / √2 outside the left plane, less than the radius
+        assert!(frustum().intersects_sphere(&s));
+        assert!(!frustum().intersects_sphere(&unit_cube().bounding_sphere().transform(&Matrix4::from_translation(vec3(-14., 0., -10.)))));
+    }
+
+    #[test]
+    fn transformed_aabbs() {
+        let frustum = frustum();
+        let at = |x: f32, z: f32| unit_cube().transform(&(Matrix4::from_translation(vec3(x, 0., z)) * Matrix4::from_angle_y(Deg(45.))));
+
+        // rotating by 45° grows the box to √2 / 2 on x and z
+        let rotated = at(0., 0.);
+        assert!((rotated.max.x - 0.5f32.sqrt()).abs() < 1e-5 && (rotated.max.z - 0.5f32.sqrt()).abs() < 1e-5);
+        assert!((rotated.max.y - 0.5).abs() < 1e-5);
+
+        // inside
+        assert!(frustum.intersects_aabb(&at(0., -10.)));
+        // outside: behind, beyond far, left of the frustum
+        assert!(!frustum.intersects_aabb(&at(0., 10.)));
+        assert!(!frustum.intersects_aabb(&at(0., -150.)));
+        assert!(!frustum.intersects_aabb(&at(-12., -10.)));
+        // center outside the left plane, corner inside
+        assert!(!frustum.contains_point(Point3::new(-10.8, 0., -10.)));
+        assert!(frustum.intersects_aabb(&at(-10.8, -10.)));
+    }
+
+    #[test]
+    fn cull_counts_meshes() {
+        let mesh = Mesh { vao: 0, vertex_count: 36, bounds: Some(unit_cube()) };
+        let mut scene = Scene::new();
+        for (name, z) in &[("front", -10.), ("behind", 10.), ("far", -150.)] {
+            let id = scene.add(name, Transform::from_translation(vec3(0., 0., *z)), None);
+            scene.node_mut(id).mesh = Some(mesh);
+        }
+        let unbounded = scene.add("unbounded", Transform::from_translation(vec3(0., 0., 10.)), None);
+        scene.node_mut(unbounded).mesh = Some(Mesh { bounds: None, ..mesh });
+        scene.add("empty", Transform::default(), None);
+        scene.update_world();
+
+        let mut visible = Vec::new();
+        let stats = cull(&scene, &frustum(), &mut visible);
+        assert_eq!(stats, CullStats { visible: 2, culled: 2 });
+        assert_eq!(visible, vec![0, unbounded]);
+    }
+}
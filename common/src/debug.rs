@@ -0,0 +1,162 @@
+// GL debug output (KHR_debug, core in 4.3): the driver reports errors,
+// deprecated usage and performance warnings through a callback, which logs
+// them to the `log` crate under the "gl" target. Contexts built with
+// WindowBuilder::debug(true) get it installed automatically.
+use std::{
+    ffi::{CStr, CString},
+    os::raw::c_void,
+    ptr,
+    sync::{atomic::{AtomicBool, Ordering}, Mutex}
+};
+
+use gl::types::{GLchar, GLenum, GLsizei, GLuint};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Severity {
+    Notification,
+    Low,
+    Medium,
+    High
+}
+
+impl Severity {
+    fn from_gl(severity: GLenum) -> Severity {
+        match severity {
+            gl::DEBUG_SEVERITY_HIGH => Severity::High,
+            gl::DEBUG_SEVERITY_MEDIUM => Severity::Medium,
+            gl::DEBUG_SEVERITY_LOW => Severity::Low,
+            _ => Severity::Notification
+        }
+    }
+
+    fn to_gl(self) -> GLenum {
+        match self {
+            Severity::High => gl::DEBUG_SEVERITY_HIGH,
+            Severity::Medium => gl::DEBUG_SEVERITY_MEDIUM,
+            Severity::Low => gl::DEBUG_SEVERITY_LOW,
+            Severity::Notification => gl::DEBUG_SEVERITY_NOTIFICATION
+        }
+    }
+
+    fn level(self) -> log::Level {
+        match self {
+            Severity::High => log::Level::Error,
+            Severity::Medium => log::Level::Warn,
+            Severity::Low => log::Level::Info,
+            Severity::Notification => log::Level::Debug
+        }
+    }
+}
+
+// set once debug output is installed, labels are skipped without it
+static ENABLED: AtomicBool = AtomicBool::new(false);
+static PANIC_ON_HIGH: AtomicBool = AtomicBool::new(false);
+// the first high severity message since the last check()
+static FIRST_HIGH: Mutex<Option<String>> = Mutex::new(None);
+
+// Installs the callback on the current context and drops messages below
+// `min_severity`. Returns false when the context has no debug output, it
+// then stays quiet. Messages are synchronous so a breakpoint in the callback
+// stops in the offending call.
+pub(crate) fn install(min_severity: Severity, panic_on_high: bool) -> bool {
+    if !gl::DebugMessageCallback::is_loaded() || !gl::DebugMessageControl::is_loaded() {
+        return false;
+    }
+    unsafe {
+        gl::Enable(gl::DEBUG_OUTPUT);
+        gl::Enable(gl::DEBUG_OUTPUT_SYNCHRONOUS);
+        gl::DebugMessageCallback(callback, ptr::null());
+        for severity in &[Severity::Notification, Severity::Low, Severity::Medium, Severity::High] {
+            let enabled = if *severity >= min_severity { gl::TRUE } else { gl::FALSE };
+            gl::DebugMessageControl(gl::DONT_CARE, gl::DONT_CARE, severity.to_gl(), 0, ptr::null(), enabled);
+        }
+    }
+    PANIC_ON_HIGH.store(panic_on_high, Ordering::SeqCst);
+    ENABLED.store(true, Ordering::SeqCst);
+    true
+}
+
+// Panics with the first high severity message (or gl_check! error) since the
+// last call, when the context was built with WindowBuilder::debug_panic(true).
+// The runner checks after every frame; unwinding out of the driver's callback
+// isn't possible, so the panic comes at the end of the frame rather than in
+// the bad call.
+pub fn check() {
+    let first = FIRST_HIGH.lock().unwrap_or_else(|e| e.into_inner()).take();
+    if let Some(message) = first {
+        if PANIC_ON_HIGH.load(Ordering::SeqCst) {
+            panic!("GL error: {}", message);
+        }
+    }
+}
+
+// Keeps the first high severity message for check().
+pub(crate) fn record_high(message: String) {
+    let mut first = FIRST_HIGH.lock().unwrap_or_else(|e| e.into_inner());
+    if first.is_none() {
+        *first = Some(message);
+    }
+}
+
+// Names a GL object in debug messages and in debuggers like RenderDoc.
+// `identifier` is the kind of object (gl::TEXTURE, gl::PROGRAM,
+// gl::FRAMEBUFFER, ...). Does nothing without debug output.
+pub fn label(identifier: GLenum, name: GLuint, label: &str) {
+    if !ENABLED.load(Ordering::Relaxed) || !gl::ObjectLabel::is_loaded() {
+        return;
+    }
+    let label = CString::new(label.replace('\0', "")).unwrap();
+    unsafe {
+        gl::ObjectLabel(identifier, name, -1, label.as_ptr());
+    }
+}
+
+fn source_name(source: GLenum) -> &'static str {
+    match source {
+        gl::DEBUG_SOURCE_API => "api",
+        gl::DEBUG_SOURCE_WINDOW_SYSTEM => "window system",
+        gl::DEBUG_SOURCE_SHADER_COMPILER => "shader compiler",
+        gl::DEBUG_SOURCE_THIRD_PARTY => "third party",
+        gl::DEBUG_SOURCE_APPLICATION => "application",
+        _ => "other"
+    }
+}
+
+fn type_name(kind: GLenum) -> &'static str {
+    match kind {
+        gl::DEBUG_TYPE_ERROR => "error",
+        gl::DEBUG_TYPE_DEPRECATED_BEHAVIOR => "deprecated",
+        gl::DEBUG_TYPE_UNDEFINED_BEHAVIOR => "undefined behavior",
+        gl::DEBUG_TYPE_PORTABILITY => "portability",
+        gl::DEBUG_TYPE_PERFORMANCE => "performance",
+        gl::DEBUG_TYPE_MARKER => "marker",
+        gl::DEBUG_TYPE_PUSH_GROUP => "push group",
+        gl::DEBUG_TYPE_POP_GROUP => "pop group",
+        _ => "other"
+    }
+}
+
+extern "system" fn callback(
+    source: GLenum,
+    kind: GLenum,
+    id: GLuint,
+    severity: GLenum,
+    _length: GLsizei,
+    message: *const GLchar,
+    _user: *mut c_void
+) {
+    let message = unsafe { CStr::from_ptr(message) }.to_string_lossy();
+    let severity = Severity::from_gl(severity);
+    log::log!(
+        target: "gl",
+        severity.level(),
+        "{} {} {:#x}: {}",
+        source_name(source),
+        type_name(kind),
+        id,
+        message.trim_end()
+    );
+    if severity == Severity::High {
+        record_high(format!("{} {}: {}", source_name(source), type_name(kind), message.trim_end()));
+    }
+}
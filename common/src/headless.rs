@@ -0,0 +1,183 @@
+// Offscreen GL context for machines without a display: EGL on Mesa's
+// surfaceless platform with a pbuffer standing in for the window's default
+// framebuffer, so chapters render exactly as they would on screen. Works on
+// llvmpipe with no GPU at all.
+use std::{ffi::CString, os::raw::{c_char, c_void}, ptr};
+
+use crate::window::{Profile, WindowBuilder, WindowError};
+
+type EGLDisplay = *mut c_void;
+type EGLConfig = *mut c_void;
+type EGLContext = *mut c_void;
+type EGLSurface = *mut c_void;
+type EGLint = i32;
+type EGLenum = u32;
+type EGLBoolean = u32;
+
+const EGL_TRUE: EGLBoolean = 1;
+const EGL_NONE: EGLint = 0x3038;
+const EGL_ALPHA_SIZE: EGLint = 0x3021;
+const EGL_BLUE_SIZE: EGLint = 0x3022;
+const EGL_GREEN_SIZE: EGLint = 0x3023;
+const EGL_RED_SIZE: EGLint = 0x3024;
+const EGL_DEPTH_SIZE: EGLint = 0x3025;
+const EGL_STENCIL_SIZE: EGLint = 0x3026;
+const EGL_SAMPLES: EGLint = 0x3031;
+const EGL_SAMPLE_BUFFERS: EGLint = 0x3032;
+const EGL_SURFACE_TYPE: EGLint = 0x3033;
+const EGL_RENDERABLE_TYPE: EGLint = 0x3040;
+const EGL_HEIGHT: EGLint = 0x3056;
+const EGL_WIDTH: EGLint = 0x3057;
+const EGL_PBUFFER_BIT: EGLint = 0x0001;
+const EGL_OPENGL_BIT: EGLint = 0x0008;
+const EGL_OPENGL_API: EGLenum = 0x30A2;
+const EGL_CONTEXT_MAJOR_VERSION: EGLint = 0x3098;
+const EGL_CONTEXT_MINOR_VERSION: EGLint = 0x30FB;
+const EGL_CONTEXT_OPENGL_PROFILE_MASK: EGLint = 0x30FD;
+const EGL_CONTEXT_OPENGL_DEBUG: EGLint = 0x31B0;
+const EGL_CONTEXT_OPENGL_CORE_PROFILE_BIT: EGLint = 0x1;
+const EGL_CONTEXT_OPENGL_COMPATIBILITY_PROFILE_BIT: EGLint = 0x2;
+const EGL_PLATFORM_SURFACELESS_MESA: EGLenum = 0x31DD;
+
+type GetPlatformDisplayExt = extern "C" fn(EGLenum, *mut c_void, *const EGLint) -> EGLDisplay;
+
+#[link(name = "EGL")]
+extern "C" {
+    fn eglGetProcAddress(name: *const c_char) -> *const c_void;
+    fn eglGetDisplay(native: *mut c_void) -> EGLDisplay;
+    fn eglInitialize(display: EGLDisplay, major: *mut EGLint, minor: *mut EGLint) -> EGLBoolean;
+    fn eglBindAPI(api: EGLenum) -> EGLBoolean;
+    fn eglChooseConfig(display: EGLDisplay, attribs: *const EGLint, configs: *mut EGLConfig, size: EGLint, count: *mut EGLint) -> EGLBoolean;
+    fn eglCreatePbufferSurface(display: EGLDisplay, config: EGLConfig, attribs: *const EGLint) -> EGLSurface;
+    fn eglCreateContext(display: EGLDisplay, config: EGLConfig, share: EGLContext, attribs: *const EGLint) -> EGLContext;
+    fn eglMakeCurrent(display: EGLDisplay, draw: EGLSurface, read: EGLSurface, context: EGLContext) -> EGLBoolean;
+    fn eglDestroySurface(display: EGLDisplay, surface: EGLSurface) -> EGLBoolean;
+    fn eglDestroyContext(display: EGLDisplay, context: EGLContext) -> EGLBoolean;
+    fn eglTerminate(display: EGLDisplay) -> EGLBoolean;
+    fn eglGetError() -> EGLint;
+}
+
+fn egl_error(call: &str) -> WindowError {
+    WindowError::Headless(format!("{} failed with EGL error {:#x}", call, unsafe { eglGetError() }))
+}
+
+pub struct Headless {
+    display: EGLDisplay,
+    context: EGLContext,
+    surface: EGLSurface,
+    width: i32,
+    height: i32
+}
+
+impl Headless {
+    // Same hints as the window would get, minus the ones that only mean
+    // something on screen (vsync, fullscreen, resizable). The pbuffer is the
+    // builder's size and never changes.
+    pub fn new(builder: &WindowBuilder) -> Result<Headless, WindowError> {
+        unsafe {
+            let display = surfaceless_display();
+            if display.is_null() {
+                return Err(WindowError::Headless("no EGL display".to_string()));
+            }
+            if eglInitialize(display, ptr::null_mut(), ptr::null_mut()) != EGL_TRUE {
+                return Err(egl_error("eglInitialize"));
+            }
+            if eglBindAPI(EGL_OPENGL_API) != EGL_TRUE {
+                return Err(egl_error("eglBindAPI"));
+            }
+
+            let samples = builder.samples.unwrap_or(0) as EGLint;
+            let config_attribs = [
+                EGL_SURFACE_TYPE, EGL_PBUFFER_BIT,
+                EGL_RENDERABLE_TYPE, EGL_OPENGL_BIT,
+                EGL_RED_SIZE, 8,
+                EGL_GREEN_SIZE, 8,
+                EGL_BLUE_SIZE, 8,
+                EGL_ALPHA_SIZE, 8,
+                EGL_DEPTH_SIZE, 24,
+                EGL_STENCIL_SIZE, 8,
+                EGL_SAMPLE_BUFFERS, if samples > 0 { 1 } else { 0 },
+                EGL_SAMPLES, samples,
+                EGL_NONE
+            ];
+            let mut config = ptr::null_mut();
+            let mut count = 0;
+            if eglChooseConfig(display, config_attribs.as_ptr(), &mut config, 1, &mut count) != EGL_TRUE || count == 0 {
+                eglTerminate(display);
+                return Err(WindowError::Headless("no EGL config for an RGBA8 pbuffer".to_string()));
+            }
+
+            let (width, height) = (builder.width as EGLint, builder.height as EGLint);
+            let surface_attribs = [EGL_WIDTH, width, EGL_HEIGHT, height, EGL_NONE];
+            let surface = eglCreatePbufferSurface(display, config, surface_attribs.as_ptr());
+            if surface.is_null() {
+                let e = egl_error("eglCreatePbufferSurface");
+                eglTerminate(display);
+                return Err(e);
+            }
+
+            let profile = match builder.profile {
+                Profile::Core => EGL_CONTEXT_OPENGL_CORE_PROFILE_BIT,
+                Profile::Compat | Profile::Any => EGL_CONTEXT_OPENGL_COMPATIBILITY_PROFILE_BIT
+            };
+            let context_attribs = [
+                EGL_CONTEXT_MAJOR_VERSION, builder.gl_version.0 as EGLint,
+                EGL_CONTEXT_MINOR_VERSION, builder.gl_version.1 as EGLint,
+                EGL_CONTEXT_OPENGL_PROFILE_MASK, profile,
+                EGL_CONTEXT_OPENGL_DEBUG, builder.debug as EGLint,
+                EGL_NONE
+            ];
+            let context = eglCreateContext(display, config, ptr::null_mut(), context_attribs.as_ptr());
+            if context.is_null() {
+                let e = egl_error("eglCreateContext");
+                eglDestroySurface(display, surface);
+                eglTerminate(display);
+                return Err(e);
+            }
+
+            let headless = Headless { display, context, surface, width, height };
+            if eglMakeCurrent(display, surface, surface, context) != EGL_TRUE {
+                return Err(egl_error("eglMakeCurrent"));
+            }
+            gl::load_with(|symbol| {
+                let name = CString::new(symbol).unwrap();
+                eglGetProcAddress(name.as_ptr())
+            });
+            if !gl::Viewport::is_loaded() {
+                return Err(WindowError::LoadGl);
+            }
+            builder.install_debug();
+            Ok(headless)
+        }
+    }
+
+    pub fn size(&self) -> (i32, i32) {
+        (self.width, self.height)
+    }
+}
+
+impl Drop for Headless {
+    fn drop(&mut self) {
+        unsafe {
+            eglMakeCurrent(self.display, ptr::null_mut(), ptr::null_mut(), ptr::null_mut());
+            eglDestroyContext(self.display, self.context);
+            eglDestroySurface(self.display, self.surface);
+            eglTerminate(self.display);
+        }
+    }
+}
+
+// The surfaceless platform needs no X or Wayland server. Drivers without it
+// still get a chance through the default display.
+unsafe fn surfaceless_display() -> EGLDisplay {
+    let name = CString::new("eglGetPlatformDisplayEXT").unwrap();
+    let get_platform_display = eglGetProcAddress(name.as_ptr());
+    if !get_platform_display.is_null() {
+        let get_platform_display: GetPlatformDisplayExt = std::mem::transmute(get_platform_display);
+        let display = get_platform_display(EGL_PLATFORM_SURFACELESS_MESA, ptr::null_mut(), ptr::null());
+        if !display.is_null() {
+            return display;
+        }
+    }
+    eglGetDisplay(ptr::null_mut())
+}
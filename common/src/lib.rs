@@ -0,0 +1,19 @@
+// Shared by the chapter crates: window/context creation, the app runner and
+// the scene graph.
+pub mod app;
+pub mod capture;
+pub mod check;
+pub mod clock;
+pub mod culling;
+pub mod debug;
+#[cfg(feature = "headless")]
+pub mod headless;
+pub mod profiler;
+pub mod recorder;
+pub mod scene;
+pub mod ui;
+pub mod uniforms;
+pub mod window;
+
+// chapters build their debug panels with this one
+pub use egui;
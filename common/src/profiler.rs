@@ -0,0 +1,364 @@
+// Scoped CPU and GPU timing. Wrap work in profile!("name", { ... }) and every
+// scope gets its CPU time and the GPU time of the commands it issued, kept as
+// a rolling min/avg/max and optionally written out as a Chrome trace
+// (chrome://tracing, or https://ui.perfetto.dev).
+//
+// GPU times come from timer queries read back FRAMES_IN_FLIGHT frames later,
+// when the GPU has long finished them, so reading never stalls the pipeline.
+// Scopes nest, which TIME_ELAPSED queries can't (only one may be active), so
+// each scope brackets its commands with a pair of TIMESTAMP queries instead.
+//
+// Profiling is off until the runner turns it on (F3, --profile or --trace),
+// scopes cost nothing meanwhile.
+use std::{
+    cell::RefCell,
+    collections::VecDeque,
+    fs,
+    path::Path,
+    time::Instant
+};
+
+use gl::types::GLuint;
+use serde_json::json;
+
+// frames between issuing a scope's queries and reading them
+const FRAMES_IN_FLIGHT: usize = 3;
+// samples the min/avg/max are taken over
+const WINDOW: usize = 120;
+
+// Profiles `$body` under `$name`, a &'static str, and evaluates to its value.
+#[macro_export]
+macro_rules! profile {
+    ($name:expr, $body:block) => {{
+        let _scope = $crate::profiler::Scope::begin($name);
+        $body
+    }};
+}
+
+thread_local! {
+    static PROFILER: RefCell<Profiler> = RefCell::new(Profiler::new());
+}
+
+// In milliseconds.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Summary {
+    pub min: f64,
+    pub avg: f64,
+    pub max: f64
+}
+
+impl Summary {
+    fn of(samples: &VecDeque<f64>) -> Option<Summary> {
+        if samples.is_empty() {
+            return None;
+        }
+        let (mut min, mut max, mut sum) = (f64::INFINITY, 0f64, 0.);
+        for &sample in samples {
+            min = min.min(sample);
+            max = max.max(sample);
+            sum += sample;
+        }
+        Some(Summary { min, avg: sum / samples.len() as f64, max })
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Stats {
+    pub name: &'static str,
+    // nesting, 0 for scopes outside any other
+    pub depth: usize,
+    pub cpu: Summary,
+    // None without timer queries or before the first results are in
+    pub gpu: Option<Summary>
+}
+
+struct Series {
+    name: &'static str,
+    depth: usize,
+    cpu: VecDeque<f64>,
+    gpu: VecDeque<f64>
+}
+
+// A scope waiting for its GPU timestamps.
+struct Pending {
+    name: &'static str,
+    queries: (GLuint, GLuint)
+}
+
+#[derive(Default)]
+struct Slot {
+    pending: Vec<Pending>,
+    // query objects are reused frame after frame
+    free: Vec<GLuint>
+}
+
+struct Trace {
+    start: Instant,
+    // GPU timestamp at `start`, in nanoseconds
+    gpu_start: i64,
+    events: Vec<serde_json::Value>
+}
+
+struct Profiler {
+    enabled: bool,
+    slots: Vec<Slot>,
+    frame: usize,
+    depth: usize,
+    series: Vec<Series>,
+    trace: Option<Trace>
+}
+
+impl Profiler {
+    fn new() -> Profiler {
+        Profiler {
+            enabled: false,
+            slots: (0..FRAMES_IN_FLIGHT).map(|_| Slot::default()).collect(),
+            frame: 0,
+            depth: 0,
+            series: Vec::new(),
+            trace: None
+        }
+    }
+
+    fn series(&mut self, name: &'static str, depth: usize) -> &mut Series {
+        let index = match self.series.iter().position(|s| s.name == name) {
+            Some(index) => index,
+            None => {
+                self.series.push(Series { name, depth, cpu: VecDeque::new(), gpu: VecDeque::new() });
+                self.series.len() - 1
+            }
+        };
+        &mut self.series[index]
+    }
+
+    fn slot(&mut self) -> &mut Slot {
+        let current = self.frame % FRAMES_IN_FLIGHT;
+        &mut self.slots[current]
+    }
+
+    // Reads the GPU times of the slot's scopes. Without `wait` results that
+    // aren't in yet are dropped rather than waited for.
+    fn resolve(&mut self, slot: usize, wait: bool) {
+        let pending: Vec<Pending> = self.slots[slot].pending.drain(..).collect();
+        for scope in pending {
+            let (start, end) = scope.queries;
+            let mut available = gl::TRUE as i32;
+            if !wait {
+                unsafe {
+                    gl::GetQueryObjectiv(end, gl::QUERY_RESULT_AVAILABLE, &mut available);
+                }
+            }
+            if available != 0 {
+                let (mut start_ns, mut end_ns) = (0u64, 0u64);
+                unsafe {
+                    gl::GetQueryObjectui64v(start, gl::QUERY_RESULT, &mut start_ns);
+                    gl::GetQueryObjectui64v(end, gl::QUERY_RESULT, &mut end_ns);
+                }
+                let ms = end_ns.saturating_sub(start_ns) as f64 / 1e6;
+                // gone when reset() came in between
+                if let Some(series) = self.series.iter_mut().find(|s| s.name == scope.name) {
+                    push_sample(&mut series.gpu, ms);
+                }
+                if let Some(trace) = &mut self.trace {
+                    let ts = (start_ns as i64 - trace.gpu_start) as f64 / 1e3;
+                    trace.events.push(event(scope.name, "gpu", 1, ts, ms * 1e3));
+                }
+            }
+            self.slots[slot].free.extend(&[start, end]);
+        }
+    }
+}
+
+fn push_sample(samples: &mut VecDeque<f64>, ms: f64) {
+    if samples.len() == WINDOW {
+        samples.pop_front();
+    }
+    samples.push_back(ms);
+}
+
+// A complete ("X") event, times in microseconds.
+fn event(name: &str, category: &str, tid: u32, ts: f64, dur: f64) -> serde_json::Value {
+    json!({ "name": name, "cat": category, "ph": "X", "pid": 0, "tid": tid, "ts": ts, "dur": dur })
+}
+
+fn gpu_timers() -> bool {
+    gl::GenQueries::is_loaded() && gl::QueryCounter::is_loaded() && gl::GetQueryObjectui64v::is_loaded()
+}
+
+// Times the CPU from begin() to drop, and the GPU commands issued meanwhile.
+// Use profile! rather than this directly.
+pub struct Scope {
+    name: &'static str,
+    start: Option<Instant>,
+    gpu_start: Option<GLuint>
+}
+
+impl Scope {
+    pub fn begin(name: &'static str) -> Scope {
+        PROFILER.with(|profiler| {
+            let mut profiler = profiler.borrow_mut();
+            if !profiler.enabled {
+                return Scope { name, start: None, gpu_start: None };
+            }
+            let depth = profiler.depth;
+            profiler.series(name, depth);
+            profiler.depth += 1;
+            let gpu_start = if gpu_timers() { Some(timestamp(profiler.slot())) } else { None };
+            Scope { name, start: Some(Instant::now()), gpu_start }
+        })
+    }
+}
+
+impl Drop for Scope {
+    fn drop(&mut self) {
+        let start = match self.start {
+            Some(start) => start,
+            None => return
+        };
+        let ms = start.elapsed().as_secs_f64() * 1e3;
+        PROFILER.with(|profiler| {
+            let mut profiler = profiler.borrow_mut();
+            profiler.depth = profiler.depth.saturating_sub(1);
+            let depth = profiler.depth;
+            push_sample(&mut profiler.series(self.name, depth).cpu, ms);
+            if let Some(gpu_start) = self.gpu_start {
+                let slot = profiler.slot();
+                let gpu_end = timestamp(slot);
+                slot.pending.push(Pending { name: self.name, queries: (gpu_start, gpu_end) });
+            }
+            if let Some(trace) = &mut profiler.trace {
+                let ts = start.saturating_duration_since(trace.start).as_secs_f64() * 1e6;
+                trace.events.push(event(self.name, "cpu", 0, ts, ms * 1e3));
+            }
+        });
+    }
+}
+
+// Records the GPU time at which the commands issued so far are done.
+fn timestamp(slot: &mut Slot) -> GLuint {
+    let query = slot.free.pop().unwrap_or_else(|| {
+        let mut query = 0;
+        unsafe {
+            gl::GenQueries(1, &mut query);
+        }
+        query
+    });
+    unsafe {
+        gl::QueryCounter(query, gl::TIMESTAMP);
+    }
+    query
+}
+
+pub fn set_enabled(enabled: bool) {
+    PROFILER.with(|profiler| profiler.borrow_mut().enabled = enabled);
+}
+
+pub fn is_enabled() -> bool {
+    PROFILER.with(|profiler| profiler.borrow().enabled)
+}
+
+// Called by the runner before each frame: picks up the GPU times of the frame
+// FRAMES_IN_FLIGHT ago and reuses its slot.
+pub fn begin_frame() {
+    PROFILER.with(|profiler| {
+        let mut profiler = profiler.borrow_mut();
+        profiler.frame += 1;
+        let slot = profiler.frame % FRAMES_IN_FLIGHT;
+        profiler.resolve(slot, false);
+    });
+}
+
+// Waits for every outstanding GPU time, before reading the final stats or
+// writing the trace.
+pub fn flush() {
+    PROFILER.with(|profiler| {
+        let mut profiler = profiler.borrow_mut();
+        for slot in 0..FRAMES_IN_FLIGHT {
+            profiler.resolve(slot, true);
+        }
+    });
+}
+
+// Deletes the query objects, for when the context is about to go.
+pub fn release() {
+    flush();
+    PROFILER.with(|profiler| {
+        for slot in &mut profiler.borrow_mut().slots {
+            if !slot.free.is_empty() {
+                unsafe {
+                    gl::DeleteQueries(slot.free.len() as i32, slot.free.as_ptr());
+                }
+                slot.free.clear();
+            }
+        }
+    });
+}
+
+// Every scope seen so far, in the order they first ran.
+pub fn stats() -> Vec<Stats> {
+    PROFILER.with(|profiler| {
+        profiler
+            .borrow()
+            .series
+            .iter()
+            .filter_map(|s| Some(Stats {
+                name: s.name,
+                depth: s.depth,
+                cpu: Summary::of(&s.cpu)?,
+                gpu: Summary::of(&s.gpu)
+            }))
+            .collect()
+    })
+}
+
+// Forgets the samples, for a fresh chapter.
+pub fn reset() {
+    PROFILER.with(|profiler| profiler.borrow_mut().series.clear());
+}
+
+// The stats as a table, nested scopes indented.
+pub fn report() -> String {
+    let mut report = format!("{:<24} {:>24} {:>24}\n", "scope (ms)", "cpu min/avg/max", "gpu min/avg/max");
+    for stats in stats() {
+        let name = format!("{}{}", "  ".repeat(stats.depth), stats.name);
+        let gpu = stats.gpu.map_or("-".to_string(), |g| format!("{:.3}/{:.3}/{:.3}", g.min, g.avg, g.max));
+        report.push_str(&format!("{:<24} {:>24} {:>24}\n",
+            name,
+            format!("{:.3}/{:.3}/{:.3}", stats.cpu.min, stats.cpu.avg, stats.cpu.max),
+            gpu
+        ));
+    }
+    report
+}
+
+// Starts collecting every scope for write_trace(). Needs a current context.
+pub fn start_trace() {
+    let mut gpu_start = 0;
+    if gpu_timers() {
+        unsafe {
+            gl::GetInteger64v(gl::TIMESTAMP, &mut gpu_start);
+        }
+    }
+    PROFILER.with(|profiler| {
+        profiler.borrow_mut().trace = Some(Trace { start: Instant::now(), gpu_start, events: Vec::new() });
+    });
+}
+
+// Writes what was collected since start_trace() as Chrome trace JSON, CPU
+// scopes on one track and GPU scopes on another, and stops collecting.
+pub fn write_trace(path: &Path) -> Result<usize, String> {
+    flush();
+    let trace = PROFILER.with(|profiler| profiler.borrow_mut().trace.take()).ok_or("no trace was started")?;
+    let count = trace.events.len();
+    let mut events = vec![
+        json!({ "name": "thread_name", "ph": "M", "pid": 0, "tid": 0, "args": { "name": "CPU" } }),
+        json!({ "name": "thread_name", "ph": "M", "pid": 0, "tid": 1, "args": { "name": "GPU" } })
+    ];
+    events.extend(trace.events);
+    let json = json!({ "traceEvents": events, "displayTimeUnit": "ms" });
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir).map_err(|e| e.to_string())?;
+    }
+    fs::write(path, json.to_string()).map_err(|e| e.to_string())?;
+    Ok(count)
+}
@@ -0,0 +1,472 @@
+// Records frames to an animated GIF, an APNG, a numbered PNG sequence or,
+// through an ffmpeg process, a video. Frames are handed to an encoder thread
+// so quantizing and compressing them does not hold up rendering, the queue in
+// between is bounded so a slow encoder holds up the renderer instead of
+// filling memory.
+use std::{
+    fs::{self, File, OpenOptions},
+    io::{self, BufWriter, Read, Seek, SeekFrom, Write},
+    path::{Path, PathBuf},
+    process::{Command, Stdio},
+    sync::mpsc::{self, Receiver, SyncSender},
+    thread::{self, JoinHandle}
+};
+
+use image::RgbImage;
+
+// frames waiting for the encoder before push() blocks
+const QUEUE: usize = 8;
+// NeuQuant speed from 1 (best palette) to 30 (fastest)
+const GIF_QUANTIZE_SPEED: i32 = 10;
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum RecordFormat {
+    Gif,
+    Apng,
+    // a directory of frame-00000.png, frame-00001.png, ...
+    PngSequence,
+    // raw RGBA frames piped into ffmpeg
+    Ffmpeg(FfmpegOptions)
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct FfmpegOptions {
+    // the executable, looked up on PATH unless it's a path
+    pub program: PathBuf,
+    // anything ffmpeg takes for -c:v
+    pub codec: String,
+    // constant rate factor, lower is better and bigger
+    pub crf: u32,
+    // file extension ffmpeg picks the container from, without the dot
+    pub container: String
+}
+
+impl FfmpegOptions {
+    // H.264 in an MP4.
+    pub fn mp4() -> FfmpegOptions {
+        FfmpegOptions { program: "ffmpeg".into(), codec: "libx264".to_string(), crf: 18, container: "mp4".to_string() }
+    }
+
+    // VP9 in a WebM.
+    pub fn webm() -> FfmpegOptions {
+        FfmpegOptions { program: "ffmpeg".into(), codec: "libvpx-vp9".to_string(), crf: 31, container: "webm".to_string() }
+    }
+
+    // The whole command line for frames of the given size, ffmpeg reads them
+    // from stdin.
+    fn command(&self, path: &Path, width: u32, height: u32, fps: f64) -> Command {
+        let mut command = Command::new(&self.program);
+        command
+            .args(["-y", "-loglevel", "error", "-f", "rawvideo", "-pix_fmt", "rgba"])
+            .args(["-video_size", &format!("{}x{}", width, height)])
+            .args(["-framerate", &fps.to_string(), "-i", "-"])
+            .args(["-c:v", &self.codec, "-crf", &self.crf.to_string()])
+            // 4:2:0 needs even sizes
+            .args(["-vf", "pad=ceil(iw/2)*2:ceil(ih/2)*2", "-pix_fmt", "yuv420p"]);
+        if self.codec.starts_with("libvpx") {
+            // without a bitrate of 0 VP8/9 treat the CRF as a quality cap
+            command.args(["-b:v", "0"]);
+        }
+        command.arg(path);
+        command
+    }
+}
+
+impl RecordFormat {
+    pub fn parse(name: &str) -> Option<RecordFormat> {
+        match name {
+            "gif" => Some(RecordFormat::Gif),
+            "apng" => Some(RecordFormat::Apng),
+            "png" => Some(RecordFormat::PngSequence),
+            "mp4" => Some(RecordFormat::Ffmpeg(FfmpegOptions::mp4())),
+            "webm" => Some(RecordFormat::Ffmpeg(FfmpegOptions::webm())),
+            _ => None
+        }
+    }
+
+    // What to add to a recording's name, nothing for the sequence directory.
+    pub fn extension(&self) -> String {
+        match self {
+            RecordFormat::Gif => ".gif".to_string(),
+            RecordFormat::Apng => ".png".to_string(),
+            RecordFormat::PngSequence => String::new(),
+            RecordFormat::Ffmpeg(options) => format!(".{}", options.container)
+        }
+    }
+}
+
+pub struct Recorder {
+    path: PathBuf,
+    // of the first frame, every frame has to match it
+    size: Option<(u32, u32)>,
+    frames: Option<SyncSender<RgbImage>>,
+    encoder: Option<JoinHandle<Result<u64, String>>>
+}
+
+impl Recorder {
+    // Every frame is shown for 1 / fps seconds. GIF delays are in hundredths
+    // of a second, so rates that don't divide 100 play slightly off.
+    pub fn start<P: Into<PathBuf>>(path: P, format: RecordFormat, fps: f64) -> Result<Recorder, String> {
+        let path = path.into();
+        match format {
+            RecordFormat::PngSequence => fs::create_dir_all(&path),
+            _ => path.parent().map_or(Ok(()), fs::create_dir_all)
+        }.map_err(|e| e.to_string())?;
+        if let RecordFormat::Ffmpeg(options) = &format {
+            // the frame size isn't known until the first frame, but a missing
+            // ffmpeg should fail here rather than when the recording stops
+            check_ffmpeg(&options.program)?;
+        }
+
+        let (sender, receiver) = mpsc::sync_channel(QUEUE);
+        let target = path.clone();
+        let encoder = thread::Builder::new()
+            .name("recorder".to_string())
+            .spawn(move || match format {
+                RecordFormat::Gif => encode_gif(&target, fps, receiver),
+                RecordFormat::Apng => encode_apng(&target, fps, receiver),
+                RecordFormat::PngSequence => encode_sequence(&target, receiver),
+                RecordFormat::Ffmpeg(options) => encode_ffmpeg(&target, &options, fps, receiver)
+            })
+            .map_err(|e| e.to_string())?;
+        Ok(Recorder { path, size: None, frames: Some(sender), encoder: Some(encoder) })
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    // Frames of another size than the first one are refused, none of the
+    // formats can change size halfway.
+    pub fn push(&mut self, frame: RgbImage) -> Result<(), String> {
+        let (width, height) = *self.size.get_or_insert(frame.dimensions());
+        if frame.dimensions() != (width, height) {
+            return Err(format!(
+                "the frame is {}x{}, the recording {}x{}",
+                frame.width(), frame.height(), width, height
+            ));
+        }
+        if let Some(frames) = &self.frames {
+            // a failed encoder has hung up, finish() reports why
+            if frames.send(frame).is_err() {
+                self.frames = None;
+            }
+        }
+        Ok(())
+    }
+
+    // Waits for the encoder to write out everything pushed so far, returns
+    // the number of frames written.
+    pub fn finish(mut self) -> Result<u64, String> {
+        self.frames.take();
+        match self.encoder.take().unwrap().join() {
+            Ok(result) => result,
+            Err(_) => Err("the encoder thread panicked".to_string())
+        }
+    }
+}
+
+impl Drop for Recorder {
+    // dropping without finish() still completes the file
+    fn drop(&mut self) {
+        self.frames.take();
+        if let Some(encoder) = self.encoder.take() {
+            let _ = encoder.join();
+        }
+    }
+}
+
+fn encode_gif(path: &Path, fps: f64, frames: Receiver<RgbImage>) -> Result<u64, String> {
+    use gif::SetParameter;
+
+    let delay = (100. / fps).round().max(1.) as u16;
+    let mut encoder = None;
+    let mut count = 0;
+    for frame in frames {
+        let (width, height) = (frame.width() as u16, frame.height() as u16);
+        if encoder.is_none() {
+            let file = File::create(path).map_err(|e| e.to_string())?;
+            let mut created = gif::Encoder::new(BufWriter::new(file), width, height, &[]).map_err(|e| e.to_string())?;
+            created.set(gif::Repeat::Infinite).map_err(|e| e.to_string())?;
+            encoder = Some(created);
+        }
+        // every frame gets its own 256 color palette
+        let mut gif_frame = gif::Frame::from_rgb_speed(width, height, &frame.into_raw(), GIF_QUANTIZE_SPEED);
+        gif_frame.delay = delay;
+        encoder.as_mut().unwrap().write_frame(&gif_frame).map_err(|e| e.to_string())?;
+        count += 1;
+    }
+    Ok(count)
+}
+
+// APNG wants the frame count in the header, before any frame. Frames are
+// written as they come under a placeholder count, which is put right once the
+// recording stops.
+fn encode_apng(path: &Path, fps: f64, frames: Receiver<RgbImage>) -> Result<u64, String> {
+    let first = match frames.recv() {
+        Ok(first) => first,
+        Err(_) => return Ok(0)
+    };
+    let (width, height) = first.dimensions();
+
+    let file = File::create(path).map_err(|e| e.to_string())?;
+    let mut encoder = png::Encoder::new(BufWriter::new(file), width, height);
+    encoder.set_color(png::ColorType::Rgb);
+    encoder.set_depth(png::BitDepth::Eight);
+    encoder.set_animated(u32::MAX, 0).map_err(|e| e.to_string())?;
+    // delays are a fraction, in milliseconds to stay within u16
+    encoder.set_frame_delay((1000. / fps).round() as u16, 1000).map_err(|e| e.to_string())?;
+    let mut writer = encoder.write_header().map_err(|e| e.to_string())?;
+    let mut count = 0;
+    for frame in Some(first).into_iter().chain(frames) {
+        writer.write_image_data(&frame).map_err(|e| e.to_string())?;
+        count += 1;
+    }
+    writer.finish().map_err(|e| e.to_string())?;
+    set_apng_frame_count(path, count).map_err(|e| format!("failed to set the frame count: {}", e))?;
+    Ok(count as u64)
+}
+
+// Rewrites the frame count in the acTL chunk and the chunk's CRC.
+fn set_apng_frame_count(path: &Path, count: u32) -> io::Result<()> {
+    let mut file = OpenOptions::new().read(true).write(true).open(path)?;
+    // chunks after the 8 byte signature are length, type, data and CRC
+    let mut offset = 8;
+    loop {
+        let mut header = [0u8; 8];
+        file.seek(SeekFrom::Start(offset))?;
+        file.read_exact(&mut header)?;
+        let length = u32::from_be_bytes([header[0], header[1], header[2], header[3]]) as u64;
+        match &header[4..] {
+            b"acTL" => break,
+            b"IDAT" | b"IEND" => return Err(io::Error::new(io::ErrorKind::InvalidData, "no acTL chunk")),
+            _ => offset += 12 + length
+        }
+    }
+
+    // the frame count, then the play count
+    let mut data = [0u8; 8];
+    file.read_exact(&mut data)?;
+    data[..4].copy_from_slice(&count.to_be_bytes());
+    let mut crc = crc32fast::Hasher::new();
+    crc.update(b"acTL");
+    crc.update(&data);
+    file.seek(SeekFrom::Start(offset + 8))?;
+    file.write_all(&data)?;
+    file.write_all(&crc.finalize().to_be_bytes())
+}
+
+fn encode_sequence(dir: &Path, frames: Receiver<RgbImage>) -> Result<u64, String> {
+    let mut count = 0;
+    for frame in frames {
+        frame.save(dir.join(format!("frame-{:05}.png", count))).map_err(|e| e.to_string())?;
+        count += 1;
+    }
+    Ok(count)
+}
+
+fn check_ffmpeg(program: &Path) -> Result<(), String> {
+    let status = Command::new(program)
+        .arg("-version")
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .status();
+    match status {
+        Ok(_) => Ok(()),
+        Err(ref e) if e.kind() == io::ErrorKind::NotFound =>
+            Err(format!("{} not found, install ffmpeg or put it on PATH to record video", program.display())),
+        Err(e) => Err(format!("failed to run {}: {}", program.display(), e))
+    }
+}
+
+fn encode_ffmpeg(path: &Path, options: &FfmpegOptions, fps: f64, frames: Receiver<RgbImage>) -> Result<u64, String> {
+    let first = match frames.recv() {
+        Ok(first) => first,
+        Err(_) => return Ok(0)
+    };
+    let (width, height) = first.dimensions();
+    let mut child = options.command(path, width, height, fps)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::null())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|e| format!("failed to run {}: {}", options.program.display(), e))?;
+    // read while the frames go in, an ffmpeg blocked on a full stderr pipe
+    // would stop reading stdin
+    let mut stderr = child.stderr.take().unwrap();
+    let errors = thread::spawn(move || {
+        let mut errors = String::new();
+        let _ = stderr.read_to_string(&mut errors);
+        errors
+    });
+
+    let written = {
+        let mut stdin = BufWriter::new(child.stdin.take().unwrap());
+        write_frames(&mut stdin, Some(first).into_iter().chain(frames)).and_then(|count| stdin.flush().map(|_| count))
+        // stdin closes here, which ends ffmpeg's input
+    };
+    let status = child.wait().map_err(|e| e.to_string())?;
+    let errors = errors.join().unwrap_or_default();
+    if !status.success() {
+        return Err(format!("{} exited with {}: {}", options.program.display(), status, errors.trim()));
+    }
+    written.map_err(|e| format!("failed to write frames to {}: {}", options.program.display(), e))
+}
+
+// Writes frames as raw RGBA rows, top to bottom with no padding, the way
+// ffmpeg's rawvideo input takes them. Returns the number of frames written.
+// Doesn't care what's on the other end, anything that reads stdin can stand
+// in for ffmpeg.
+pub fn write_frames<W: Write, I: IntoIterator<Item = RgbImage>>(out: &mut W, frames: I) -> io::Result<u64> {
+    let mut count = 0;
+    let mut rgba = Vec::new();
+    for frame in frames {
+        rgba.clear();
+        for pixel in frame.into_raw().chunks(3) {
+            rgba.extend_from_slice(pixel);
+            rgba.push(255);
+        }
+        out.write_all(&rgba)?;
+        count += 1;
+    }
+    Ok(count)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn frames(count: usize, width: u32, height: u32) -> Vec<RgbImage> {
+        (0..count).map(|i| RgbImage::from_pixel(width, height, image::Rgb([i as u8, 0, 255]))).collect()
+    }
+
+    fn temp_path(name: &str) -> PathBuf {
+        std::env::temp_dir().join(format!("recorder-{}-{}", std::process::id(), name))
+    }
+
+    // stands in for ffmpeg's stdin
+    #[derive(Default)]
+    struct CountingWriter {
+        bytes: usize,
+        writes: usize
+    }
+
+    impl Write for CountingWriter {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            self.bytes += buf.len();
+            self.writes += 1;
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn write_frames_writes_every_byte() {
+        let (count, width, height) = (7, 33, 17);
+        let mut out = CountingWriter::default();
+        assert_eq!(write_frames(&mut out, frames(count, width, height)).unwrap(), count as u64);
+        // RGB frames go out as RGBA
+        assert_eq!(out.bytes, count * (width * height) as usize * 4);
+        assert!(out.writes >= count);
+    }
+
+    #[test]
+    fn write_frames_pads_rgb_to_rgba() {
+        let mut out = Vec::new();
+        let frame = RgbImage::from_raw(2, 1, vec![1, 2, 3, 4, 5, 6]).unwrap();
+        assert_eq!(write_frames(&mut out, vec![frame]).unwrap(), 1);
+        assert_eq!(out, vec![1, 2, 3, 255, 4, 5, 6, 255]);
+    }
+
+    #[test]
+    fn frames_of_another_size_are_refused() {
+        let path = temp_path("resize");
+        let mut recorder = Recorder::start(&path, RecordFormat::PngSequence, 30.).unwrap();
+        let mut sizes = frames(1, 4, 3).into_iter().chain(frames(1, 4, 3)).chain(frames(1, 3, 4));
+        assert_eq!(recorder.push(sizes.next().unwrap()), Ok(()));
+        assert_eq!(recorder.push(sizes.next().unwrap()), Ok(()));
+        assert_eq!(recorder.push(sizes.next().unwrap()), Err("the frame is 3x4, the recording 4x3".to_string()));
+        assert_eq!(recorder.finish(), Ok(2));
+        fs::remove_dir_all(&path).unwrap();
+    }
+
+    #[test]
+    fn apng_frame_count_is_set_after_streaming() {
+        let path = temp_path("animation.png");
+        let mut recorder = Recorder::start(&path, RecordFormat::Apng, 30.).unwrap();
+        for frame in frames(20, 5, 3) {
+            recorder.push(frame).unwrap();
+        }
+        assert_eq!(recorder.finish(), Ok(20));
+
+        let mut reader = png::Decoder::new(File::open(&path).unwrap()).read_info().unwrap();
+        let control = reader.info().animation_control.unwrap();
+        assert_eq!((control.num_frames, control.num_plays), (20, 0));
+        let mut buffer = vec![0; reader.output_buffer_size()];
+        let mut decoded = 0;
+        while reader.next_frame(&mut buffer).is_ok() {
+            assert_eq!(&buffer[..3], &[decoded, 0, 255]);
+            decoded += 1;
+        }
+        assert_eq!(decoded, 20);
+        fs::remove_file(&path).unwrap();
+    }
+
+    // An executable shell script standing in for ffmpeg. It answers the
+    // -version check and otherwise gets ffmpeg's arguments, the output path
+    // last.
+    #[cfg(unix)]
+    fn stub_ffmpeg(name: &str, body: &str) -> FfmpegOptions {
+        use std::os::unix::fs::PermissionsExt;
+
+        let program = temp_path(name);
+        let script = format!("#!/bin/sh\n[ \"$1\" = -version ] && exit 0\nfor out; do :; done\n{}\n", body);
+        fs::write(&program, script).unwrap();
+        fs::set_permissions(&program, fs::Permissions::from_mode(0o755)).unwrap();
+        FfmpegOptions { program, ..FfmpegOptions::mp4() }
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn ffmpeg_gets_every_byte() {
+        // more on stderr than a pipe holds before reading any input, and more
+        // input than a pipe holds
+        let options = stub_ffmpeg("count.sh", "head -c 200000 /dev/zero | tr '\\0' x >&2\nwc -c < /dev/stdin > \"$out\"");
+        let path = temp_path("count.mp4");
+        let mut recorder = Recorder::start(&path, RecordFormat::Ffmpeg(options.clone()), 30.).unwrap();
+        for frame in frames(12, 64, 48) {
+            recorder.push(frame).unwrap();
+        }
+        assert_eq!(recorder.finish(), Ok(12));
+        let bytes: usize = fs::read_to_string(&path).unwrap().trim().parse().unwrap();
+        assert_eq!(bytes, 12 * 64 * 48 * 4);
+        fs::remove_file(&path).unwrap();
+        fs::remove_file(&options.program).unwrap();
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn ffmpeg_failures_report_stderr() {
+        let options = stub_ffmpeg("fail.sh", "cat > /dev/null\necho 'Unknown encoder libx264' >&2\nexit 3");
+        let path = temp_path("fail.mp4");
+        let mut recorder = Recorder::start(&path, RecordFormat::Ffmpeg(options.clone()), 30.).unwrap();
+        for frame in frames(3, 8, 8) {
+            recorder.push(frame).unwrap();
+        }
+        let error = recorder.finish().unwrap_err();
+        assert!(error.contains("exit status: 3"), "{}", error);
+        assert!(error.ends_with(": Unknown encoder libx264"), "{}", error);
+        fs::remove_file(&options.program).unwrap();
+    }
+
+    #[test]
+    fn missing_ffmpeg_fails_to_start() {
+        let options = FfmpegOptions { program: temp_path("no-such-ffmpeg"), ..FfmpegOptions::mp4() };
+        let error = Recorder::start(temp_path("missing.mp4"), RecordFormat::Ffmpeg(options), 30.).err().unwrap();
+        assert!(error.contains("not found"), "{}", error);
+    }
+}
@@ -0,0 +1,348 @@
+use cgmath::{Deg, Euler, Matrix4, Quaternion, Vector3, vec3};
+use cgmath::prelude::*;
+
+use crate::culling::Aabb;
+use crate::ui::drag_vec3;
+
+pub type NodeId = usize;
+
+// Called once per Scene::update with the node's local transform, the
+// elapsed time and the frame delta, both in seconds.
+pub type Behavior = Box<dyn FnMut(&mut Transform, f32, f32)>;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Transform {
+    pub translation: Vector3<f32>,
+    pub rotation: Quaternion<f32>,
+    pub scale: Vector3<f32>
+}
+
+impl Transform {
+    pub fn from_translation(translation: Vector3<f32>) -> Transform {
+        Transform { translation, ..Transform::default() }
+    }
+
+    pub fn matrix(&self) -> Matrix4<f32> {
+        Matrix4::from_translation(self.translation)
+            * Matrix4::from(self.rotation)
+            * Matrix4::from_nonuniform_scale(self.scale.x, self.scale.y, self.scale.z)
+    }
+
+    // Editors for a debug panel, the rotation as Euler angles in degrees.
+    // Returns true when anything changed.
+    pub fn ui(&mut self, ui: &mut egui::Ui) -> bool {
+        let euler = Euler::from(self.rotation);
+        let mut angles = [Deg::from(euler.x).0, Deg::from(euler.y).0, Deg::from(euler.z).0];
+        let mut changed = drag_vec3(ui, "translation", self.translation.as_mut(), 0.01);
+        if drag_vec3(ui, "rotation", &mut angles, 0.5) {
+            self.rotation = Quaternion::from(Euler::new(Deg(angles[0]), Deg(angles[1]), Deg(angles[2])));
+            changed = true;
+        }
+        changed |= drag_vec3(ui, "scale", self.scale.as_mut(), 0.01);
+        changed
+    }
+}
+
+impl Default for Transform {
+    fn default() -> Transform {
+        Transform {
+            translation: vec3(0., 0., 0.),
+            rotation: Quaternion::one(),
+            scale: vec3(1., 1., 1.)
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct Mesh {
+    pub vao: u32,
+    pub vertex_count: i32,
+    // local space, used for culling and picking
+    pub bounds: Option<Aabb>
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct Material {
+    // bound to TEXTURE0, TEXTURE1, ... in order
+    pub textures: Vec<u32>
+}
+
+impl Material {
+    /// # Safety
+    ///
+    /// Needs a current GL context in which every texture of the material is
+    /// still alive.
+    pub unsafe fn bind(&self) {
+        for (unit, texture) in self.textures.iter().enumerate() {
+            gl::ActiveTexture(gl::TEXTURE0 + unit as u32);
+            gl::BindTexture(gl::TEXTURE_2D, *texture);
+        }
+    }
+}
+
+pub struct Node {
+    pub name: String,
+    pub mesh: Option<Mesh>,
+    pub material: Option<Material>,
+    pub behavior: Option<Behavior>,
+    local: Transform,
+    world: Matrix4<f32>,
+    parent: Option<NodeId>,
+    children: Vec<NodeId>,
+    dirty: bool
+}
+
+impl Node {
+    pub fn local(&self) -> &Transform {
+        &self.local
+    }
+
+    // only up to date after Scene::update_world
+    pub fn world(&self) -> &Matrix4<f32> {
+        &self.world
+    }
+
+    pub fn parent(&self) -> Option<NodeId> {
+        self.parent
+    }
+
+    pub fn children(&self) -> &[NodeId] {
+        &self.children
+    }
+}
+
+#[derive(Default)]
+pub struct Scene {
+    nodes: Vec<Node>
+}
+
+impl Scene {
+    pub fn new() -> Scene {
+        Scene { nodes: Vec::new() }
+    }
+
+    pub fn add(&mut self, name: &str, local: Transform, parent: Option<NodeId>) -> NodeId {
+        let id = self.nodes.len();
+        self.nodes.push(Node {
+            name: name.to_string(),
+            mesh: None,
+            material: None,
+            behavior: None,
+            local,
+            world: Matrix4::identity(),
+            parent: None,
+            children: Vec::new(),
+            dirty: true
+        });
+        self.set_parent(id, parent);
+        id
+    }
+
+    pub fn node(&self, id: NodeId) -> &Node {
+        &self.nodes[id]
+    }
+
+    pub fn node_mut(&mut self, id: NodeId) -> &mut Node {
+        &mut self.nodes[id]
+    }
+
+    pub fn nodes(&self) -> impl Iterator<Item = (NodeId, &Node)> {
+        self.nodes.iter().enumerate()
+    }
+
+    pub fn len(&self) -> usize {
+        self.nodes.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.nodes.is_empty()
+    }
+
+    pub fn find(&self, name: &str) -> Option<NodeId> {
+        self.nodes.iter().position(|n| n.name == name)
+    }
+
+    // Marks the node dirty, its children pick that up in update_world.
+    pub fn transform_mut(&mut self, id: NodeId) -> &mut Transform {
+        self.nodes[id].dirty = true;
+        &mut self.nodes[id].local
+    }
+
+    pub fn set_parent(&mut self, id: NodeId, parent: Option<NodeId>) {
+        if let Some(old) = self.nodes[id].parent.take() {
+            self.nodes[old].children.retain(|&c| c != id);
+        }
+        if let Some(parent) = parent {
+            let mut ancestor = Some(parent);
+            while let Some(a) = ancestor {
+                assert!(a != id, "{} can not be parented to its own descendant", self.nodes[id].name);
+                ancestor = self.nodes[a].parent;
+            }
+            self.nodes[parent].children.push(id);
+        }
+        self.nodes[id].parent = parent;
+        self.nodes[id].dirty = true;
+    }
+
+    // Runs behaviors, then refreshes world matrices.
+    pub fn update(&mut self, time: f32, dt: f32) {
+        for node in &mut self.nodes {
+            if let Some(behavior) = node.behavior.as_mut() {
+                behavior(&mut node.local, time, dt);
+                node.dirty = true;
+            }
+        }
+        self.update_world();
+    }
+
+    // Recomputes the world matrix of every dirty node and of everything
+    // below it, clean subtrees are left alone.
+    pub fn update_world(&mut self) {
+        let mut stack: Vec<(NodeId, bool)> = self.nodes
+            .iter()
+            .enumerate()
+            .filter(|(_, n)| n.parent.is_none())
+            .map(|(id, _)| (id, false))
+            .collect();
+
+        while let Some((id, parent_changed)) = stack.pop() {
+            let changed = parent_changed || self.nodes[id].dirty;
+            if changed {
+                let parent_world = match self.nodes[id].parent {
+                    Some(parent) => self.nodes[parent].world,
+                    None => Matrix4::identity()
+                };
+                let node = &mut self.nodes[id];
+                node.world = parent_world * node.local.matrix();
+                node.dirty = false;
+            }
+            stack.extend(self.nodes[id].children.iter().map(|&c| (c, changed)));
+        }
+    }
+
+    /// # Safety
+    ///
+    /// Same as `draw_nodes`.
+    pub unsafe fn draw<F: FnMut(NodeId, &Matrix4<f32>)>(&self, set_model: F) {
+        self.draw_nodes(0..self.nodes.len(), set_model);
+    }
+
+    /// # Safety
+    ///
+    /// Needs a current GL context with the program meant for these nodes in
+    /// use, and the VAOs of their meshes and textures of their materials
+    /// still alive.
+    pub unsafe fn draw_nodes<I, F>(&self, ids: I, mut set_model: F)
+    where
+        I: IntoIterator<Item = NodeId>,
+        F: FnMut(NodeId, &Matrix4<f32>)
+    {
+        for id in ids {
+            let node = &self.nodes[id];
+            if let Some(mesh) = node.mesh {
+                if let Some(material) = &node.material {
+                    material.bind();
+                }
+                set_model(id, &node.world);
+                gl::BindVertexArray(mesh.vao);
+                gl::DrawArrays(gl::TRIANGLES, 0, mesh.vertex_count);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use cgmath::Rad;
+
+    use super::*;
+
+    fn assert_matrix_eq(actual: &Matrix4<f32>, expected: &Matrix4<f32>) {
+        let (a, e): (&[f32; 16], &[f32; 16]) = (actual.as_ref(), expected.as_ref());
+        for (a, e) in a.iter().zip(e) {
+            assert!((a - e).abs() < 1e-5, "\n{:?}\n!=\n{:?}", actual, expected);
+        }
+    }
+
+    // a chain of three nodes and their local matrices, multiplied out by hand
+    fn chain() -> (Scene, [NodeId; 3], [Matrix4<f32>; 3]) {
+        let root = Transform {
+            translation: vec3(1., 2., 3.),
+            rotation: Quaternion::from_angle_y(Deg(90.)),
+            scale: vec3(2., 2., 2.)
+        };
+        let child = Transform {
+            translation: vec3(0., 1., 0.),
+            rotation: Quaternion::from_angle_x(Deg(30.)),
+            ..Transform::default()
+        };
+        let grandchild = Transform {
+            translation: vec3(0., 0., -1.),
+            scale: vec3(1., 2., 1.),
+            ..Transform::default()
+        };
+        let matrices = [
+            Matrix4::from_translation(vec3(1., 2., 3.)) * Matrix4::from_angle_y(Rad::from(Deg(90.))) * Matrix4::from_scale(2.),
+            Matrix4::from_translation(vec3(0., 1., 0.)) * Matrix4::from_angle_x(Rad::from(Deg(30.))),
+            Matrix4::from_translation(vec3(0., 0., -1.)) * Matrix4::from_nonuniform_scale(1., 2., 1.)
+        ];
+
+        let mut scene = Scene::new();
+        let a = scene.add("root", root, None);
+        let b = scene.add("child", child, Some(a));
+        let c = scene.add("grandchild", grandchild, Some(b));
+        (scene, [a, b, c], matrices)
+    }
+
+    #[test]
+    fn local_matrix() {
+        let (scene, [a, ..], [ma, ..]) = chain();
+        assert_matrix_eq(&scene.node(a).local().matrix(), &ma);
+    }
+
+    #[test]
+    fn world_matrices_propagate_down_the_chain() {
+        let (mut scene, [a, b, c], [ma, mb, mc]) = chain();
+        scene.update_world();
+        assert_matrix_eq(scene.node(a).world(), &ma);
+        assert_matrix_eq(scene.node(b).world(), &(ma * mb));
+        assert_matrix_eq(scene.node(c).world(), &(ma * mb * mc));
+    }
+
+    #[test]
+    fn changing_a_parent_updates_its_descendants() {
+        let (mut scene, [a, b, c], [_, mb, mc]) = chain();
+        scene.update_world();
+        scene.transform_mut(a).translation = vec3(-5., 0., 0.);
+        scene.update_world();
+
+        let ma = Matrix4::from_translation(vec3(-5., 0., 0.)) * Matrix4::from_angle_y(Rad::from(Deg(90.))) * Matrix4::from_scale(2.);
+        assert_matrix_eq(scene.node(b).world(), &(ma * mb));
+        assert_matrix_eq(scene.node(c).world(), &(ma * mb * mc));
+    }
+
+    #[test]
+    fn reparenting() {
+        let (mut scene, [a, b, c], [ma, mb, mc]) = chain();
+        scene.update_world();
+
+        scene.set_parent(c, Some(a));
+        scene.update_world();
+        assert_eq!(scene.node(c).parent(), Some(a));
+        assert_eq!(scene.node(a).children(), &[b, c]);
+        assert!(scene.node(b).children().is_empty());
+        assert_matrix_eq(scene.node(c).world(), &(ma * mc));
+
+        scene.set_parent(b, None);
+        scene.update_world();
+        assert_eq!(scene.node(a).children(), &[c]);
+        assert_matrix_eq(scene.node(b).world(), &mb);
+    }
+
+    #[test]
+    #[should_panic(expected = "can not be parented to its own descendant")]
+    fn parenting_to_a_descendant_panics() {
+        let (mut scene, [a, _, c], _) = chain();
+        scene.set_parent(a, Some(c));
+    }
+}
@@ -0,0 +1,596 @@
+// Immediate-mode debug UI, egui drawn over the chapter. F2 shows it, the
+// runner then feeds it the GLFW events before the chapter sees them and calls
+// App::ui every frame for the chapter's panels, then shows the uniform
+// inspector. Events the UI takes (clicks on a window, typing into a field)
+// are kept from the chapter.
+//
+// Everything is drawn in gamma space, the way egui's colors and font
+// textures come, so FRAMEBUFFER_SRGB is off while painting.
+use std::{collections::HashMap, ffi::CString, mem, os::raw::c_void, ptr, time::Instant};
+
+use egui::{
+    epaint::{Primitive, Vertex},
+    ClippedPrimitive, Color32, Event, ImageData, Modifiers, MouseWheelUnit, PointerButton, Pos2, RawInput, Rect,
+    TextureFilter, TextureId, TexturesDelta, ViewportId, ViewportInfo
+};
+use gl::types::{GLenum, GLint, GLsizei, GLsizeiptr, GLuint};
+use glfw::{Action, Key, MouseButton, WindowEvent};
+
+use crate::app::{App, Context};
+use crate::debug;
+use crate::gl_check;
+use crate::uniforms;
+
+const VERTEX_SHADER: &str = r#"
+#version 330 core
+layout (location = 0) in vec2 aPos;
+layout (location = 1) in vec2 aTexCoord;
+layout (location = 2) in vec4 aColor;
+
+uniform vec2 screenSize;
+
+out vec2 texCoord;
+out vec4 color;
+
+void main() {
+    gl_Position = vec4(2.0 * aPos.x / screenSize.x - 1.0, 1.0 - 2.0 * aPos.y / screenSize.y, 0.0, 1.0);
+    texCoord = aTexCoord;
+    color = aColor;
+}
+"#;
+
+const FRAGMENT_SHADER: &str = r#"
+#version 330 core
+in vec2 texCoord;
+in vec4 color;
+
+uniform sampler2D font;
+
+out vec4 FragColor;
+
+void main() {
+    FragColor = color * texture(font, texCoord);
+}
+"#;
+
+// The runner's side of the UI: input, the egui context and the painter.
+pub(crate) struct DebugUi {
+    egui: egui::Context,
+    painter: Painter,
+    pub visible: bool,
+    events: Vec<Event>,
+    modifiers: Modifiers,
+    pointer: Pos2,
+    start: Instant
+}
+
+impl DebugUi {
+    pub fn new() -> DebugUi {
+        DebugUi {
+            egui: egui::Context::default(),
+            painter: Painter::new(),
+            visible: false,
+            events: Vec::new(),
+            modifiers: Modifiers::default(),
+            pointer: Pos2::ZERO,
+            start: Instant::now()
+        }
+    }
+
+    // Takes in a window event, returns true when the UI used it and the
+    // chapter shouldn't get it.
+    pub fn on_event(&mut self, ctx: &Context, event: &WindowEvent) -> bool {
+        match *event {
+            WindowEvent::CursorPos(x, y) => {
+                // points are screen coordinates, see frame()
+                self.pointer = Pos2::new(x as f32, y as f32);
+                self.events.push(Event::PointerMoved(self.pointer));
+                self.egui.wants_pointer_input()
+            }
+            WindowEvent::CursorEnter(false) => {
+                self.events.push(Event::PointerGone);
+                false
+            }
+            WindowEvent::MouseButton(button, action, modifiers) => {
+                self.modifiers = convert_modifiers(modifiers);
+                let button = match button {
+                    MouseButton::Button1 => PointerButton::Primary,
+                    MouseButton::Button2 => PointerButton::Secondary,
+                    MouseButton::Button3 => PointerButton::Middle,
+                    _ => return false
+                };
+                self.events.push(Event::PointerButton {
+                    pos: self.pointer,
+                    button,
+                    pressed: action == Action::Press,
+                    modifiers: self.modifiers
+                });
+                self.egui.is_pointer_over_area() || self.egui.wants_pointer_input()
+            }
+            WindowEvent::Scroll(x, y) => {
+                self.events.push(Event::MouseWheel {
+                    unit: MouseWheelUnit::Line,
+                    delta: egui::vec2(x as f32, y as f32),
+                    modifiers: self.modifiers
+                });
+                self.egui.is_pointer_over_area()
+            }
+            WindowEvent::Char(c) => {
+                self.events.push(Event::Text(c.to_string()));
+                self.egui.wants_keyboard_input()
+            }
+            WindowEvent::Key(key, _, action, modifiers) => {
+                self.modifiers = convert_modifiers(modifiers);
+                let pressed = action != Action::Release;
+                if pressed && self.modifiers.command {
+                    match key {
+                        Key::C => self.events.push(Event::Copy),
+                        Key::X => self.events.push(Event::Cut),
+                        Key::V => {
+                            if let Some(text) = ctx.clipboard() {
+                                self.events.push(Event::Paste(text));
+                            }
+                        }
+                        _ => {}
+                    }
+                }
+                if let Some(key) = convert_key(key) {
+                    self.events.push(Event::Key {
+                        key,
+                        physical_key: None,
+                        pressed,
+                        repeat: action == Action::Repeat,
+                        modifiers: self.modifiers
+                    });
+                }
+                self.egui.wants_keyboard_input()
+            }
+            WindowEvent::Focus(focused) => {
+                self.events.push(Event::WindowFocused(focused));
+                false
+            }
+            _ => false
+        }
+    }
+
+    // Runs the chapter's panels and draws them over the frame.
+    pub fn frame(&mut self, ctx: &mut Context, app: &mut dyn App) {
+        let (width, height) = ctx.size();
+        let (fb_width, fb_height) = ctx.framebuffer_size();
+        // one point per screen coordinate, so cursor positions need no scaling
+        let pixels_per_point = fb_width as f32 / width.max(1) as f32;
+
+        let mut input = RawInput {
+            screen_rect: Some(Rect::from_min_size(Pos2::ZERO, egui::vec2(width as f32, height as f32))),
+            time: Some(self.start.elapsed().as_secs_f64()),
+            modifiers: self.modifiers,
+            events: mem::take(&mut self.events),
+            focused: true,
+            ..RawInput::default()
+        };
+        input.viewports.insert(ViewportId::ROOT, ViewportInfo {
+            native_pixels_per_point: Some(pixels_per_point),
+            ..ViewportInfo::default()
+        });
+
+        let output = self.egui.run(input, |egui| {
+            app.ui(ctx, egui);
+            uniforms::inspector(egui);
+        });
+        if !output.platform_output.copied_text.is_empty() {
+            ctx.set_clipboard(&output.platform_output.copied_text);
+        }
+        let primitives = self.egui.tessellate(output.shapes, output.pixels_per_point);
+        self.painter.paint(&primitives, &output.textures_delta, output.pixels_per_point, (fb_width, fb_height));
+    }
+}
+
+impl Drop for DebugUi {
+    fn drop(&mut self) {
+        self.painter.release();
+    }
+}
+
+fn convert_modifiers(modifiers: glfw::Modifiers) -> Modifiers {
+    let ctrl = modifiers.contains(glfw::Modifiers::Control);
+    Modifiers {
+        alt: modifiers.contains(glfw::Modifiers::Alt),
+        ctrl,
+        shift: modifiers.contains(glfw::Modifiers::Shift),
+        mac_cmd: false,
+        command: ctrl
+    }
+}
+
+fn convert_key(key: Key) -> Option<egui::Key> {
+    use egui::Key as K;
+    Some(match key {
+        Key::Left => K::ArrowLeft,
+        Key::Right => K::ArrowRight,
+        Key::Up => K::ArrowUp,
+        Key::Down => K::ArrowDown,
+        Key::Escape => K::Escape,
+        Key::Tab => K::Tab,
+        Key::Backspace => K::Backspace,
+        Key::Enter | Key::KpEnter => K::Enter,
+        Key::Space => K::Space,
+        Key::Insert => K::Insert,
+        Key::Delete => K::Delete,
+        Key::Home => K::Home,
+        Key::End => K::End,
+        Key::PageUp => K::PageUp,
+        Key::PageDown => K::PageDown,
+        Key::Minus | Key::KpSubtract => K::Minus,
+        Key::Equal | Key::KpAdd => K::Plus,
+        Key::Num0 | Key::Kp0 => K::Num0,
+        Key::Num1 | Key::Kp1 => K::Num1,
+        Key::Num2 | Key::Kp2 => K::Num2,
+        Key::Num3 | Key::Kp3 => K::Num3,
+        Key::Num4 | Key::Kp4 => K::Num4,
+        Key::Num5 | Key::Kp5 => K::Num5,
+        Key::Num6 | Key::Kp6 => K::Num6,
+        Key::Num7 | Key::Kp7 => K::Num7,
+        Key::Num8 | Key::Kp8 => K::Num8,
+        Key::Num9 | Key::Kp9 => K::Num9,
+        // for select all, undo and the like
+        Key::A => K::A,
+        Key::Y => K::Y,
+        Key::Z => K::Z,
+        _ => return None
+    })
+}
+
+// Draws egui's meshes with the GL state the chapter had put back afterwards.
+struct Painter {
+    program: GLuint,
+    screen_size: GLint,
+    vao: GLuint,
+    vbo: GLuint,
+    ebo: GLuint,
+    textures: HashMap<TextureId, GLuint>
+}
+
+impl Painter {
+    fn new() -> Painter {
+        let mut painter = Painter {
+            program: link_program(),
+            screen_size: 0,
+            vao: 0,
+            vbo: 0,
+            ebo: 0,
+            textures: HashMap::new()
+        };
+        unsafe {
+            let name = CString::new("screenSize").unwrap();
+            painter.screen_size = gl::GetUniformLocation(painter.program, name.as_ptr());
+
+            gl::GenVertexArrays(1, &mut painter.vao);
+            gl::GenBuffers(1, &mut painter.vbo);
+            gl::GenBuffers(1, &mut painter.ebo);
+            let mut previous = 0;
+            gl::GetIntegerv(gl::VERTEX_ARRAY_BINDING, &mut previous);
+            gl::BindVertexArray(painter.vao);
+            gl::BindBuffer(gl::ARRAY_BUFFER, painter.vbo);
+            gl::BindBuffer(gl::ELEMENT_ARRAY_BUFFER, painter.ebo);
+            let stride = mem::size_of::<Vertex>() as GLsizei;
+            gl::VertexAttribPointer(0, 2, gl::FLOAT, gl::FALSE, stride, ptr::null());
+            gl::EnableVertexAttribArray(0);
+            gl::VertexAttribPointer(1, 2, gl::FLOAT, gl::FALSE, stride, (2 * mem::size_of::<f32>()) as *const c_void);
+            gl::EnableVertexAttribArray(1);
+            gl::VertexAttribPointer(2, 4, gl::UNSIGNED_BYTE, gl::TRUE, stride, (4 * mem::size_of::<f32>()) as *const c_void);
+            gl::EnableVertexAttribArray(2);
+            gl::BindVertexArray(previous as GLuint);
+        }
+        debug::label(gl::PROGRAM, painter.program, "debug ui");
+        debug::label(gl::VERTEX_ARRAY, painter.vao, "debug ui");
+        debug::label(gl::BUFFER, painter.vbo, "debug ui vertices");
+        debug::label(gl::BUFFER, painter.ebo, "debug ui indices");
+        painter
+    }
+
+    fn paint(&mut self, primitives: &[ClippedPrimitive], delta: &TexturesDelta, pixels_per_point: f32, size: (i32, i32)) {
+        let saved = unsafe { SavedState::save() };
+        unsafe {
+            gl::ActiveTexture(gl::TEXTURE0);
+        }
+        for (id, image) in &delta.set {
+            self.upload(*id, image);
+        }
+
+        unsafe {
+            gl::Viewport(0, 0, size.0, size.1);
+            gl::Disable(gl::DEPTH_TEST);
+            gl::Disable(gl::CULL_FACE);
+            gl::Disable(gl::FRAMEBUFFER_SRGB);
+            gl::Enable(gl::SCISSOR_TEST);
+            gl::PolygonMode(gl::FRONT_AND_BACK, gl::FILL);
+            // egui's colors are premultiplied
+            gl::Enable(gl::BLEND);
+            gl::BlendEquation(gl::FUNC_ADD);
+            gl::BlendFuncSeparate(gl::ONE, gl::ONE_MINUS_SRC_ALPHA, gl::ONE_MINUS_DST_ALPHA, gl::ONE);
+
+            gl::UseProgram(self.program);
+            gl::Uniform2f(self.screen_size, size.0 as f32 / pixels_per_point, size.1 as f32 / pixels_per_point);
+            gl::BindVertexArray(self.vao);
+            gl::BindBuffer(gl::ARRAY_BUFFER, self.vbo);
+
+            for ClippedPrimitive { clip_rect, primitive } in primitives {
+                let mesh = match primitive {
+                    Primitive::Mesh(mesh) => mesh,
+                    // only egui's own widgets are used
+                    Primitive::Callback(_) => continue
+                };
+                let texture = match self.textures.get(&mesh.texture_id) {
+                    Some(texture) => *texture,
+                    None => continue
+                };
+
+                // clip rect in points, top down, to pixels bottom up
+                let min_x = (clip_rect.min.x * pixels_per_point).round().clamp(0., size.0 as f32) as i32;
+                let min_y = (clip_rect.min.y * pixels_per_point).round().clamp(0., size.1 as f32) as i32;
+                let max_x = (clip_rect.max.x * pixels_per_point).round().clamp(min_x as f32, size.0 as f32) as i32;
+                let max_y = (clip_rect.max.y * pixels_per_point).round().clamp(min_y as f32, size.1 as f32) as i32;
+                if max_x == min_x || max_y == min_y {
+                    continue;
+                }
+                gl::Scissor(min_x, size.1 - max_y, max_x - min_x, max_y - min_y);
+
+                gl::BindTexture(gl::TEXTURE_2D, texture);
+                gl_check!(gl::BufferData(
+                    gl::ARRAY_BUFFER,
+                    (mesh.vertices.len() * mem::size_of::<Vertex>()) as GLsizeiptr,
+                    mesh.vertices.as_ptr() as *const c_void,
+                    gl::STREAM_DRAW
+                ));
+                gl_check!(gl::BufferData(
+                    gl::ELEMENT_ARRAY_BUFFER,
+                    (mesh.indices.len() * mem::size_of::<u32>()) as GLsizeiptr,
+                    mesh.indices.as_ptr() as *const c_void,
+                    gl::STREAM_DRAW
+                ));
+                gl_check!(gl::DrawElements(gl::TRIANGLES, mesh.indices.len() as GLsizei, gl::UNSIGNED_INT, ptr::null()));
+            }
+        }
+
+        for id in &delta.free {
+            if let Some(texture) = self.textures.remove(id) {
+                unsafe {
+                    gl::DeleteTextures(1, &texture);
+                }
+            }
+        }
+        unsafe {
+            saved.restore();
+        }
+    }
+
+    // Whole images or patches of them, as egui's font atlas grows.
+    fn upload(&mut self, id: TextureId, delta: &egui::epaint::ImageDelta) {
+        let pixels: Vec<Color32> = match &delta.image {
+            ImageData::Color(image) => image.pixels.clone(),
+            ImageData::Font(image) => image.srgba_pixels(None).collect()
+        };
+        let [width, height] = delta.image.size();
+        let filter = |filter: TextureFilter| match filter {
+            TextureFilter::Nearest => gl::NEAREST as GLint,
+            TextureFilter::Linear => gl::LINEAR as GLint
+        };
+
+        let texture = *self.textures.entry(id).or_insert_with(|| {
+            let mut texture = 0;
+            unsafe {
+                gl::GenTextures(1, &mut texture);
+                // a texture only exists to be named once bound
+                gl::BindTexture(gl::TEXTURE_2D, texture);
+            }
+            debug::label(gl::TEXTURE, texture, &format!("debug ui {:?}", id));
+            texture
+        });
+        unsafe {
+            gl::BindTexture(gl::TEXTURE_2D, texture);
+            gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_MIN_FILTER, filter(delta.options.minification));
+            gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_MAG_FILTER, filter(delta.options.magnification));
+            gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_WRAP_S, gl::CLAMP_TO_EDGE as GLint);
+            gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_WRAP_T, gl::CLAMP_TO_EDGE as GLint);
+            gl::PixelStorei(gl::UNPACK_ALIGNMENT, 4);
+            match delta.pos {
+                Some([x, y]) => gl_check!(gl::TexSubImage2D(
+                    gl::TEXTURE_2D,
+                    0,
+                    x as GLint,
+                    y as GLint,
+                    width as GLsizei,
+                    height as GLsizei,
+                    gl::RGBA,
+                    gl::UNSIGNED_BYTE,
+                    pixels.as_ptr() as *const c_void
+                )),
+                None => gl_check!(gl::TexImage2D(
+                    gl::TEXTURE_2D,
+                    0,
+                    gl::RGBA8 as GLint,
+                    width as GLsizei,
+                    height as GLsizei,
+                    0,
+                    gl::RGBA,
+                    gl::UNSIGNED_BYTE,
+                    pixels.as_ptr() as *const c_void
+                ))
+            }
+        }
+    }
+
+    fn release(&mut self) {
+        unsafe {
+            for (_, texture) in self.textures.drain() {
+                gl::DeleteTextures(1, &texture);
+            }
+            gl::DeleteBuffers(1, &self.vbo);
+            gl::DeleteBuffers(1, &self.ebo);
+            gl::DeleteVertexArrays(1, &self.vao);
+            gl::DeleteProgram(self.program);
+        }
+    }
+}
+
+fn link_program() -> GLuint {
+    unsafe {
+        let compile = |kind: GLenum, source: &str| {
+            let shader = gl::CreateShader(kind);
+            let source = CString::new(source).unwrap();
+            gl::ShaderSource(shader, 1, &source.as_ptr(), ptr::null());
+            gl::CompileShader(shader);
+            shader
+        };
+        let (vertex, fragment) = (compile(gl::VERTEX_SHADER, VERTEX_SHADER), compile(gl::FRAGMENT_SHADER, FRAGMENT_SHADER));
+        let program = gl::CreateProgram();
+        gl::AttachShader(program, vertex);
+        gl::AttachShader(program, fragment);
+        gl::LinkProgram(program);
+        let mut success = 0;
+        gl::GetProgramiv(program, gl::LINK_STATUS, &mut success);
+        if success == 0 {
+            let mut log = vec![0u8; 1024];
+            let mut length = 0;
+            gl::GetProgramInfoLog(program, log.len() as GLsizei, &mut length, log.as_mut_ptr() as *mut _);
+            log.truncate(length.max(0) as usize);
+            println!("ERROR::DEBUG_UI::LINKING_FAILED\n{}", String::from_utf8_lossy(&log));
+        }
+        gl::DeleteShader(vertex);
+        gl::DeleteShader(fragment);
+        program
+    }
+}
+
+// What painting changes, chapters set most of it once in init() and expect
+// it to stay.
+struct SavedState {
+    enabled: [(GLenum, bool); 5],
+    program: GLint,
+    vao: GLint,
+    array_buffer: GLint,
+    active_texture: GLint,
+    texture: GLint,
+    viewport: [GLint; 4],
+    scissor: [GLint; 4],
+    polygon_mode: [GLint; 2],
+    blend: [GLint; 5],
+    unpack_alignment: GLint
+}
+
+impl SavedState {
+    unsafe fn save() -> SavedState {
+        let get = |name: GLenum| {
+            let mut value = 0;
+            gl::GetIntegerv(name, &mut value);
+            value
+        };
+        let enabled = |cap: GLenum| (cap, gl::IsEnabled(cap) == gl::TRUE);
+        let mut state = SavedState {
+            enabled: [
+                enabled(gl::DEPTH_TEST),
+                enabled(gl::CULL_FACE),
+                enabled(gl::BLEND),
+                enabled(gl::SCISSOR_TEST),
+                enabled(gl::FRAMEBUFFER_SRGB)
+            ],
+            program: get(gl::CURRENT_PROGRAM),
+            vao: get(gl::VERTEX_ARRAY_BINDING),
+            array_buffer: get(gl::ARRAY_BUFFER_BINDING),
+            active_texture: get(gl::ACTIVE_TEXTURE),
+            texture: 0,
+            viewport: [0; 4],
+            scissor: [0; 4],
+            polygon_mode: [0; 2],
+            blend: [
+                get(gl::BLEND_SRC_RGB),
+                get(gl::BLEND_DST_RGB),
+                get(gl::BLEND_SRC_ALPHA),
+                get(gl::BLEND_DST_ALPHA),
+                get(gl::BLEND_EQUATION_RGB)
+            ],
+            unpack_alignment: get(gl::UNPACK_ALIGNMENT)
+        };
+        gl::ActiveTexture(gl::TEXTURE0);
+        state.texture = get(gl::TEXTURE_BINDING_2D);
+        gl::GetIntegerv(gl::VIEWPORT, state.viewport.as_mut_ptr());
+        gl::GetIntegerv(gl::SCISSOR_BOX, state.scissor.as_mut_ptr());
+        gl::GetIntegerv(gl::POLYGON_MODE, state.polygon_mode.as_mut_ptr());
+        state
+    }
+
+    unsafe fn restore(&self) {
+        for &(cap, enabled) in &self.enabled {
+            if enabled {
+                gl::Enable(cap);
+            } else {
+                gl::Disable(cap);
+            }
+        }
+        gl::UseProgram(self.program as GLuint);
+        gl::BindVertexArray(self.vao as GLuint);
+        gl::BindBuffer(gl::ARRAY_BUFFER, self.array_buffer as GLuint);
+        gl::BindTexture(gl::TEXTURE_2D, self.texture as GLuint);
+        gl::ActiveTexture(self.active_texture as GLenum);
+        let [x, y, width, height] = self.viewport;
+        gl::Viewport(x, y, width, height);
+        let [x, y, width, height] = self.scissor;
+        gl::Scissor(x, y, width, height);
+        gl::PolygonMode(gl::FRONT_AND_BACK, self.polygon_mode[0] as GLenum);
+        let [src_rgb, dst_rgb, src_alpha, dst_alpha, equation] = self.blend;
+        gl::BlendFuncSeparate(src_rgb as GLenum, dst_rgb as GLenum, src_alpha as GLenum, dst_alpha as GLenum);
+        gl::BlendEquation(equation as GLenum);
+        gl::PixelStorei(gl::UNPACK_ALIGNMENT, self.unpack_alignment);
+    }
+}
+
+// State chapters commonly want to flip from their panel: the clear color,
+// wireframe and the depth test. Call apply() at the start of render().
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct RenderState {
+    pub clear_color: [f32; 4],
+    pub wireframe: bool,
+    pub depth_test: bool
+}
+
+impl RenderState {
+    pub fn new(clear_color: [f32; 4], depth_test: bool) -> RenderState {
+        RenderState { clear_color, wireframe: false, depth_test }
+    }
+
+    pub fn ui(&mut self, ui: &mut egui::Ui) {
+        ui.horizontal(|ui| {
+            ui.color_edit_button_rgba_unmultiplied(&mut self.clear_color);
+            ui.label("clear color");
+        });
+        ui.checkbox(&mut self.wireframe, "wireframe");
+        ui.checkbox(&mut self.depth_test, "depth test");
+    }
+
+    pub fn apply(&self) {
+        let [r, g, b, a] = self.clear_color;
+        unsafe {
+            gl::ClearColor(r, g, b, a);
+            gl::PolygonMode(gl::FRONT_AND_BACK, if self.wireframe { gl::LINE } else { gl::FILL });
+            if self.depth_test {
+                gl::Enable(gl::DEPTH_TEST);
+            } else {
+                gl::Disable(gl::DEPTH_TEST);
+            }
+        }
+    }
+}
+
+// A labelled row of three drag values, for positions, angles and scales.
+// Returns true when one changed.
+pub fn drag_vec3(ui: &mut egui::Ui, label: &str, value: &mut [f32; 3], speed: f32) -> bool {
+    ui.horizontal(|ui| {
+        let mut changed = false;
+        for component in value.iter_mut() {
+            changed |= ui.add(egui::DragValue::new(component).speed(speed).max_decimals(3)).changed();
+        }
+        ui.label(label);
+        changed
+    })
+    .inner
+}
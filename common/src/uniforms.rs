@@ -0,0 +1,494 @@
+// Uniform inspector. Chapters register their programs with the vertex and
+// fragment files they were built from, and the F2 debug UI gets a window
+// listing every active uniform of every program, found by reflection, with
+// its type and current value. Edits override what the chapter sets: Shader
+// setters call reapply() after setting a uniform, so an edited `percent`
+// sticks although the chapter sets it every frame.
+//
+// Edits are kept by file names and uniform name rather than by program, so
+// they outlive the program. While the window is open a saved shader file is
+// recompiled and the program relinked in place, the uniform values it had
+// put back and the edits applied again. Starting a chapter again builds new
+// programs that pick the edits up the same way.
+use std::{
+    cell::RefCell,
+    collections::HashMap,
+    env,
+    ffi::CString,
+    fs,
+    mem,
+    path::PathBuf,
+    ptr,
+    time::SystemTime
+};
+
+use gl::types::{GLchar, GLenum, GLint, GLsizei, GLuint};
+
+thread_local! {
+    static REGISTRY: RefCell<Registry> = RefCell::new(Registry::default());
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Value {
+    Float(f32),
+    Vec2([f32; 2]),
+    Vec3([f32; 3]),
+    Vec4([f32; 4]),
+    Int(i32),
+    UInt(u32),
+    Bool(bool),
+    Mat3([f32; 9]),
+    Mat4([f32; 16])
+}
+
+// An active uniform as reported by the program. Arrays are reported by
+// their first element.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Uniform {
+    pub name: String,
+    // gl::FLOAT_VEC3, gl::SAMPLER_2D, ...
+    pub kind: GLenum,
+    // array length, 1 for plain uniforms
+    pub size: i32,
+    pub location: GLint
+}
+
+#[derive(Clone)]
+struct Program {
+    id: GLuint,
+    // the paths as given, for the inspector
+    name: String,
+    // absolute, every chapter has a shaders/vertex.glsl
+    vertex: PathBuf,
+    fragment: PathBuf,
+    // of the newer file, for noticing saves
+    modified: Option<SystemTime>
+}
+
+impl Program {
+    fn key(&self) -> String {
+        format!("{} + {}", self.vertex.display(), self.fragment.display())
+    }
+}
+
+#[derive(Default)]
+struct Registry {
+    programs: Vec<Program>,
+    // by Program::key() and uniform name
+    overrides: HashMap<(String, String), Value>
+}
+
+impl Registry {
+    fn program(&self, id: GLuint) -> Option<&Program> {
+        self.programs.iter().find(|p| p.id == id)
+    }
+}
+
+// Lists `program` in the inspector and applies the edits made to a program
+// built from the same files.
+pub fn register(program: GLuint, vertex_path: &str, fragment_path: &str) {
+    let absolute = |path: &str| env::current_dir().map(|dir| dir.join(path)).unwrap_or_else(|_| PathBuf::from(path));
+    let entry = Program {
+        id: program,
+        name: format!("{} + {}", vertex_path, fragment_path),
+        vertex: absolute(vertex_path),
+        fragment: absolute(fragment_path),
+        modified: None
+    };
+    let modified = last_modified(&entry);
+    REGISTRY.with(|registry| {
+        let mut registry = registry.borrow_mut();
+        registry.programs.retain(|p| p.id != program);
+        registry.programs.push(Program { modified, ..entry });
+    });
+    apply_overrides(program);
+}
+
+// Forgets the programs, for a fresh chapter. The edits stay.
+pub fn reset() {
+    REGISTRY.with(|registry| registry.borrow_mut().programs.clear());
+}
+
+// Sets `name` back to its edited value, if it was edited. Called by Shader
+// setters right after setting the chapter's value.
+pub fn reapply(program: GLuint, name: &str) {
+    let value = REGISTRY.with(|registry| {
+        let registry = registry.borrow();
+        if registry.overrides.is_empty() {
+            return None;
+        }
+        let key = registry.program(program)?.key();
+        registry.overrides.get(&(key, name.to_string())).copied()
+    });
+    if let Some(value) = value {
+        let name = CString::new(name).unwrap();
+        unsafe {
+            write(program, gl::GetUniformLocation(program, name.as_ptr()), value);
+        }
+    }
+}
+
+// The program's active uniforms, uniform block members left out.
+pub fn reflect(program: GLuint) -> Vec<Uniform> {
+    let mut uniforms = Vec::new();
+    unsafe {
+        let (mut count, mut max_length) = (0, 0);
+        gl::GetProgramiv(program, gl::ACTIVE_UNIFORMS, &mut count);
+        gl::GetProgramiv(program, gl::ACTIVE_UNIFORM_MAX_LENGTH, &mut max_length);
+        let mut buffer = vec![0u8; max_length.max(1) as usize];
+        for index in 0..count.max(0) as GLuint {
+            let (mut length, mut size, mut kind) = (0, 0, 0);
+            gl::GetActiveUniform(
+                program,
+                index,
+                buffer.len() as GLsizei,
+                &mut length,
+                &mut size,
+                &mut kind,
+                buffer.as_mut_ptr() as *mut GLchar
+            );
+            let name = String::from_utf8_lossy(&buffer[..length.max(0) as usize]).into_owned();
+            let location = gl::GetUniformLocation(program, CString::new(name.as_str()).unwrap().as_ptr());
+            if location < 0 {
+                continue;
+            }
+            uniforms.push(Uniform { name, kind, size, location });
+        }
+    }
+    uniforms
+}
+
+// The uniform's current value, None for types the inspector can't edit.
+pub fn read(program: GLuint, uniform: &Uniform) -> Option<Value> {
+    let (program, location) = (program, uniform.location);
+    let floats = |n: usize| {
+        let mut v = [0f32; 16];
+        unsafe {
+            gl::GetUniformfv(program, location, v.as_mut_ptr());
+        }
+        let mut out = [0f32; 16];
+        out[..n].copy_from_slice(&v[..n]);
+        out
+    };
+    let int = || {
+        let mut v = 0;
+        unsafe {
+            gl::GetUniformiv(program, location, &mut v);
+        }
+        v
+    };
+    Some(match uniform.kind {
+        gl::FLOAT => Value::Float(floats(1)[0]),
+        gl::FLOAT_VEC2 => {
+            let v = floats(2);
+            Value::Vec2([v[0], v[1]])
+        }
+        gl::FLOAT_VEC3 => {
+            let v = floats(3);
+            Value::Vec3([v[0], v[1], v[2]])
+        }
+        gl::FLOAT_VEC4 => {
+            let v = floats(4);
+            Value::Vec4([v[0], v[1], v[2], v[3]])
+        }
+        gl::FLOAT_MAT3 => {
+            let mut m = [0f32; 9];
+            m.copy_from_slice(&floats(9)[..9]);
+            Value::Mat3(m)
+        }
+        gl::FLOAT_MAT4 => Value::Mat4(floats(16)),
+        gl::UNSIGNED_INT => {
+            let mut v = 0;
+            unsafe {
+                gl::GetUniformuiv(program, location, &mut v);
+            }
+            Value::UInt(v)
+        }
+        gl::BOOL => Value::Bool(int() != 0),
+        gl::INT | gl::SAMPLER_2D | gl::SAMPLER_3D | gl::SAMPLER_CUBE | gl::UNSIGNED_INT_SAMPLER_2D => Value::Int(int()),
+        _ => return None
+    })
+}
+
+// Sets a uniform of a program that needn't be in use.
+unsafe fn write(program: GLuint, location: GLint, value: Value) {
+    if location < 0 {
+        return;
+    }
+    let mut current = 0;
+    gl::GetIntegerv(gl::CURRENT_PROGRAM, &mut current);
+    gl::UseProgram(program);
+    match value {
+        Value::Float(v) => gl::Uniform1f(location, v),
+        Value::Vec2(v) => gl::Uniform2fv(location, 1, v.as_ptr()),
+        Value::Vec3(v) => gl::Uniform3fv(location, 1, v.as_ptr()),
+        Value::Vec4(v) => gl::Uniform4fv(location, 1, v.as_ptr()),
+        Value::Int(v) => gl::Uniform1i(location, v),
+        Value::UInt(v) => gl::Uniform1ui(location, v),
+        Value::Bool(v) => gl::Uniform1i(location, v as GLint),
+        Value::Mat3(v) => gl::UniformMatrix3fv(location, 1, gl::FALSE, v.as_ptr()),
+        Value::Mat4(v) => gl::UniformMatrix4fv(location, 1, gl::FALSE, v.as_ptr())
+    }
+    gl::UseProgram(current as GLuint);
+}
+
+fn apply_overrides(program: GLuint) {
+    let overrides: Vec<(String, Value)> = REGISTRY.with(|registry| {
+        let registry = registry.borrow();
+        let key = match registry.program(program) {
+            Some(p) => p.key(),
+            None => return Vec::new()
+        };
+        registry.overrides
+            .iter()
+            .filter(|((k, _), _)| *k == key)
+            .map(|((_, name), value)| (name.clone(), *value))
+            .collect()
+    });
+    for (name, value) in overrides {
+        let name = CString::new(name).unwrap();
+        unsafe {
+            write(program, gl::GetUniformLocation(program, name.as_ptr()), value);
+        }
+    }
+}
+
+fn last_modified(program: &Program) -> Option<SystemTime> {
+    let time = |path: &PathBuf| fs::metadata(path).and_then(|m| m.modified()).ok();
+    time(&program.vertex).max(time(&program.fragment))
+}
+
+// Recompiles the program's files and relinks it in place, so the chapter's
+// id stays valid. Linking resets every uniform, the values from before are
+// put back by name and the edits applied again. A program that doesn't
+// compile or link is left as it was.
+fn reload(program: GLuint, vertex_path: &PathBuf, fragment_path: &PathBuf) -> Result<(), String> {
+    let source = |path: &PathBuf| {
+        fs::read_to_string(path)
+            .map_err(|e| format!("{}: {}", path.display(), e))
+            .and_then(|source| CString::new(source).map_err(|e| e.to_string()))
+    };
+    let (vertex_source, fragment_source) = (source(vertex_path)?, source(fragment_path)?);
+
+    unsafe {
+        let vertex = compile(gl::VERTEX_SHADER, &vertex_source).map_err(|e| format!("{}: {}", vertex_path.display(), e))?;
+        let fragment = match compile(gl::FRAGMENT_SHADER, &fragment_source) {
+            Ok(fragment) => fragment,
+            Err(e) => {
+                gl::DeleteShader(vertex);
+                return Err(format!("{}: {}", fragment_path.display(), e));
+            }
+        };
+
+        // a failed link would lose the program's executable, try on a
+        // scratch program first
+        let scratch = gl::CreateProgram();
+        let linked = link(scratch, vertex, fragment);
+        gl::DeleteProgram(scratch);
+        if let Err(e) = linked {
+            gl::DeleteShader(vertex);
+            gl::DeleteShader(fragment);
+            return Err(e);
+        }
+
+        let values: Vec<(String, Value)> = reflect(program)
+            .iter()
+            .filter_map(|u| Some((u.name.clone(), read(program, u)?)))
+            .collect();
+
+        let mut attached = [0; 8];
+        let mut count = 0;
+        gl::GetAttachedShaders(program, attached.len() as GLsizei, &mut count, attached.as_mut_ptr());
+        for &shader in &attached[..count.max(0) as usize] {
+            gl::DetachShader(program, shader);
+        }
+        let relinked = link(program, vertex, fragment);
+        gl::DetachShader(program, vertex);
+        gl::DetachShader(program, fragment);
+        gl::DeleteShader(vertex);
+        gl::DeleteShader(fragment);
+        relinked?;
+
+        let uniforms = reflect(program);
+        for (name, value) in values {
+            if let Some(uniform) = uniforms.iter().find(|u| u.name == name) {
+                if read(program, uniform).map(|v| same_type(v, value)) == Some(true) {
+                    write(program, uniform.location, value);
+                }
+            }
+        }
+    }
+    apply_overrides(program);
+    Ok(())
+}
+
+fn same_type(a: Value, b: Value) -> bool {
+    mem::discriminant(&a) == mem::discriminant(&b)
+}
+
+unsafe fn compile(kind: GLenum, source: &CString) -> Result<GLuint, String> {
+    let shader = gl::CreateShader(kind);
+    gl::ShaderSource(shader, 1, &source.as_ptr(), ptr::null());
+    gl::CompileShader(shader);
+    let mut success = 0;
+    gl::GetShaderiv(shader, gl::COMPILE_STATUS, &mut success);
+    if success == 0 {
+        let log = info_log(shader, gl::GetShaderiv, gl::GetShaderInfoLog);
+        gl::DeleteShader(shader);
+        return Err(log);
+    }
+    Ok(shader)
+}
+
+unsafe fn link(program: GLuint, vertex: GLuint, fragment: GLuint) -> Result<(), String> {
+    gl::AttachShader(program, vertex);
+    gl::AttachShader(program, fragment);
+    gl::LinkProgram(program);
+    let mut success = 0;
+    gl::GetProgramiv(program, gl::LINK_STATUS, &mut success);
+    if success == 0 {
+        return Err(info_log(program, gl::GetProgramiv, gl::GetProgramInfoLog));
+    }
+    Ok(())
+}
+
+unsafe fn info_log(
+    object: GLuint,
+    get: unsafe fn(GLuint, GLenum, *mut GLint),
+    get_log: unsafe fn(GLuint, GLsizei, *mut GLsizei, *mut GLchar)
+) -> String {
+    let mut length = 0;
+    get(object, gl::INFO_LOG_LENGTH, &mut length);
+    let mut log = vec![0u8; length.max(1) as usize];
+    let mut written = 0;
+    get_log(object, log.len() as GLsizei, &mut written, log.as_mut_ptr() as *mut GLchar);
+    log.truncate(written.max(0) as usize);
+    String::from_utf8_lossy(&log).trim_end().to_string()
+}
+
+fn type_name(kind: GLenum) -> &'static str {
+    match kind {
+        gl::FLOAT => "float",
+        gl::FLOAT_VEC2 => "vec2",
+        gl::FLOAT_VEC3 => "vec3",
+        gl::FLOAT_VEC4 => "vec4",
+        gl::FLOAT_MAT2 => "mat2",
+        gl::FLOAT_MAT3 => "mat3",
+        gl::FLOAT_MAT4 => "mat4",
+        gl::INT => "int",
+        gl::INT_VEC2 => "ivec2",
+        gl::INT_VEC3 => "ivec3",
+        gl::INT_VEC4 => "ivec4",
+        gl::UNSIGNED_INT => "uint",
+        gl::BOOL => "bool",
+        gl::SAMPLER_2D => "sampler2D",
+        gl::SAMPLER_3D => "sampler3D",
+        gl::SAMPLER_CUBE => "samplerCube",
+        gl::UNSIGNED_INT_SAMPLER_2D => "usampler2D",
+        _ => "other"
+    }
+}
+
+// The inspector window, drawn by the debug UI after the chapter's panels.
+// Also relinks programs whose files were saved since the last look.
+pub fn inspector(ctx: &egui::Context) {
+    let programs: Vec<Program> = REGISTRY.with(|registry| registry.borrow().programs.clone());
+    if programs.is_empty() {
+        return;
+    }
+
+    for program in &programs {
+        let saved = REGISTRY.with(|registry| {
+            let mut registry = registry.borrow_mut();
+            let entry = registry.programs.iter_mut().find(|p| p.id == program.id)?;
+            let modified = last_modified(entry);
+            if modified <= entry.modified {
+                return None;
+            }
+            entry.modified = modified;
+            Some(())
+        });
+        if saved.is_some() {
+            match reload(program.id, &program.vertex, &program.fragment) {
+                Ok(()) => println!("reloaded {}", program.name),
+                Err(e) => println!("ERROR::SHADER::RELOAD {}\n{}", program.name, e)
+            }
+        }
+    }
+
+    egui::Window::new("uniforms").default_open(false).show(ctx, |ui| {
+        egui::ScrollArea::vertical().show(ui, |ui| {
+            for program in &programs {
+                egui::CollapsingHeader::new(program.name.as_str()).id_salt(program.id).show(ui, |ui| {
+                    program_ui(ui, program.id, &program.key());
+                });
+            }
+        });
+    });
+}
+
+fn program_ui(ui: &mut egui::Ui, program: GLuint, key: &str) {
+    egui::Grid::new(("uniforms", program)).num_columns(3).striped(true).show(ui, |ui| {
+        for uniform in reflect(program) {
+            let override_key = (key.to_string(), uniform.name.clone());
+            let overridden = REGISTRY.with(|registry| registry.borrow().overrides.contains_key(&override_key));
+            let name = if uniform.size > 1 { format!("{}[{}]", uniform.name, uniform.size) } else { uniform.name.clone() };
+            ui.label(if overridden { egui::RichText::new(name).strong() } else { egui::RichText::new(name) });
+            ui.label(type_name(uniform.kind));
+
+            match read(program, &uniform) {
+                Some(mut value) => {
+                    ui.horizontal(|ui| {
+                        if value_ui(ui, &mut value) {
+                            REGISTRY.with(|registry| registry.borrow_mut().overrides.insert(override_key.clone(), value));
+                            unsafe {
+                                write(program, uniform.location, value);
+                            }
+                        }
+                        // the chapter's own value is back with its next set
+                        if overridden && ui.small_button("reset").clicked() {
+                            REGISTRY.with(|registry| registry.borrow_mut().overrides.remove(&override_key));
+                        }
+                    });
+                }
+                None => {
+                    ui.label("-");
+                }
+            }
+            ui.end_row();
+        }
+    });
+}
+
+// Returns true when the value was edited.
+fn value_ui(ui: &mut egui::Ui, value: &mut Value) -> bool {
+    let drag = |ui: &mut egui::Ui, v: &mut f32| ui.add(egui::DragValue::new(v).speed(0.01).max_decimals(3)).changed();
+    let drags = |ui: &mut egui::Ui, vs: &mut [f32]| vs.iter_mut().fold(false, |changed, v| drag(ui, v) | changed);
+    match value {
+        Value::Float(v) => drag(ui, v),
+        Value::Vec2(v) => drags(ui, v),
+        Value::Vec3(v) => drags(ui, v),
+        Value::Vec4(v) => drags(ui, v),
+        Value::Int(v) => ui.add(egui::DragValue::new(v)).changed(),
+        Value::UInt(v) => ui.add(egui::DragValue::new(v)).changed(),
+        Value::Bool(v) => ui.checkbox(v, "").changed(),
+        // column major, shown a row at a time
+        Value::Mat3(m) => matrix_ui(ui, m, 3, drag),
+        Value::Mat4(m) => matrix_ui(ui, m, 4, drag)
+    }
+}
+
+fn matrix_ui<F: Fn(&mut egui::Ui, &mut f32) -> bool>(ui: &mut egui::Ui, m: &mut [f32], n: usize, drag: F) -> bool {
+    ui.vertical(|ui| {
+        let mut changed = false;
+        for row in 0..n {
+            ui.horizontal(|ui| {
+                for column in 0..n {
+                    changed |= drag(ui, &mut m[column * n + row]);
+                }
+            });
+        }
+        changed
+    })
+    .inner
+}
@@ -0,0 +1,230 @@
+use std::{error, fmt, sync::{mpsc::Receiver, Mutex}};
+
+use glfw::{self, Context, Glfw, Window, WindowEvent};
+
+use crate::debug::{self, Severity};
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Profile {
+    Core,
+    Compat,
+    Any
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Fullscreen {
+    Primary,
+    // index into the connected monitors, 0 is the primary one
+    Monitor(usize)
+}
+
+// What WindowBuilder::build() returns: GLFW, the window with its context
+// current and the window's event receiver.
+pub type BuiltWindow = (Glfw, Window, Receiver<(f64, WindowEvent)>);
+
+#[derive(Debug)]
+pub enum WindowError {
+    Init(glfw::InitError),
+    NoMonitor(Fullscreen),
+    // with GLFW's description of what went wrong
+    CreateWindow(String),
+    // the context was created but the GL functions could not be loaded
+    LoadGl,
+    // the offscreen context could not be created
+    Headless(String)
+}
+
+impl fmt::Display for WindowError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            WindowError::Init(e) => write!(f, "Failed to initialize GLFW: {:?}", e),
+            WindowError::NoMonitor(choice) => write!(f, "No monitor for {:?}", choice),
+            WindowError::CreateWindow(e) => write!(f, "Failed to create GLFW window: {}", e),
+            WindowError::LoadGl => write!(f, "Failed to load OpenGL functions"),
+            WindowError::Headless(e) => write!(f, "Failed to create headless context: {}", e)
+        }
+    }
+}
+
+impl error::Error for WindowError {}
+
+// Creates the window with a current GL context, key and framebuffer size
+// polling, and the GL functions loaded.
+#[derive(Clone, Debug)]
+pub struct WindowBuilder {
+    pub(crate) title: String,
+    pub(crate) width: u32,
+    pub(crate) height: u32,
+    pub(crate) gl_version: (u32, u32),
+    pub(crate) profile: Profile,
+    pub(crate) samples: Option<u32>,
+    pub(crate) vsync: bool,
+    pub(crate) resizable: bool,
+    pub(crate) fullscreen: Option<Fullscreen>,
+    pub(crate) debug: bool,
+    pub(crate) debug_severity: Severity,
+    pub(crate) debug_panic: bool,
+    pub(crate) srgb: bool
+}
+
+impl WindowBuilder {
+    pub fn new(title: &str, width: u32, height: u32) -> WindowBuilder {
+        WindowBuilder {
+            title: title.to_string(),
+            width,
+            height,
+            gl_version: (3, 3),
+            profile: Profile::Core,
+            samples: None,
+            vsync: true,
+            resizable: true,
+            fullscreen: None,
+            debug: false,
+            debug_severity: Severity::Low,
+            debug_panic: false,
+            srgb: false
+        }
+    }
+
+    pub fn title(mut self, title: &str) -> WindowBuilder {
+        self.title = title.to_string();
+        self
+    }
+
+    pub fn size(mut self, width: u32, height: u32) -> WindowBuilder {
+        self.width = width;
+        self.height = height;
+        self
+    }
+
+    pub fn gl_version(mut self, major: u32, minor: u32) -> WindowBuilder {
+        self.gl_version = (major, minor);
+        self
+    }
+
+    pub fn profile(mut self, profile: Profile) -> WindowBuilder {
+        self.profile = profile;
+        self
+    }
+
+    // MSAA samples of the default framebuffer, None to disable
+    pub fn samples(mut self, samples: Option<u32>) -> WindowBuilder {
+        self.samples = samples;
+        self
+    }
+
+    pub fn vsync(mut self, vsync: bool) -> WindowBuilder {
+        self.vsync = vsync;
+        self
+    }
+
+    pub fn resizable(mut self, resizable: bool) -> WindowBuilder {
+        self.resizable = resizable;
+        self
+    }
+
+    // Fullscreen windows use the monitor's current video mode instead of
+    // the requested size.
+    pub fn fullscreen(mut self, fullscreen: Option<Fullscreen>) -> WindowBuilder {
+        self.fullscreen = fullscreen;
+        self
+    }
+
+    // Debug context with GL debug output logged under the "gl" target, see
+    // the debug module.
+    pub fn debug(mut self, debug: bool) -> WindowBuilder {
+        self.debug = debug;
+        self
+    }
+
+    // Least severe debug message that still gets logged, Low by default.
+    pub fn debug_severity(mut self, severity: Severity) -> WindowBuilder {
+        self.debug_severity = severity;
+        self
+    }
+
+    // Panic at the end of the frame that caused the first high severity
+    // message, for tests. Needs debug(true).
+    pub fn debug_panic(mut self, panic: bool) -> WindowBuilder {
+        self.debug_panic = panic;
+        self
+    }
+
+    // sRGB capable default framebuffer, for FRAMEBUFFER_SRGB
+    pub fn srgb(mut self, srgb: bool) -> WindowBuilder {
+        self.srgb = srgb;
+        self
+    }
+
+    pub fn build(&self) -> Result<BuiltWindow, WindowError> {
+        let mut glfw = glfw::init(RECORD_ERRORS).map_err(WindowError::Init)?;
+        glfw.window_hint(glfw::WindowHint::ContextVersion(self.gl_version.0, self.gl_version.1));
+        glfw.window_hint(glfw::WindowHint::OpenGlProfile(match self.profile {
+            Profile::Core => glfw::OpenGlProfileHint::Core,
+            Profile::Compat => glfw::OpenGlProfileHint::Compat,
+            Profile::Any => glfw::OpenGlProfileHint::Any
+        }));
+        #[cfg(target_os = "macos")]
+        glfw.window_hint(glfw::WindowHint::OpenGlForwardCompat(true));
+        glfw.window_hint(glfw::WindowHint::Samples(self.samples));
+        glfw.window_hint(glfw::WindowHint::Resizable(self.resizable));
+        glfw.window_hint(glfw::WindowHint::OpenGlDebugContext(self.debug));
+        glfw.window_hint(glfw::WindowHint::SRgbCapable(self.srgb));
+
+        let (mut window, events) = match self.fullscreen {
+            None => glfw
+                .create_window(self.width, self.height, &self.title, glfw::WindowMode::Windowed)
+                .ok_or_else(create_window_error)?,
+            Some(choice) => glfw.with_connected_monitors(|glfw, monitors| {
+                // GLFW always lists the primary monitor first
+                let monitor = match choice {
+                    Fullscreen::Primary => monitors.first(),
+                    Fullscreen::Monitor(index) => monitors.get(index)
+                }.ok_or(WindowError::NoMonitor(choice))?;
+                let (width, height) = monitor
+                    .get_video_mode()
+                    .map_or((self.width, self.height), |mode| (mode.width, mode.height));
+                glfw.create_window(width, height, &self.title, glfw::WindowMode::FullScreen(monitor))
+                    .ok_or_else(create_window_error)
+            })?
+        };
+
+        window.make_current();
+        window.set_key_polling(true);
+        window.set_framebuffer_size_polling(true);
+        glfw.set_swap_interval(if self.vsync { glfw::SwapInterval::Sync(1) } else { glfw::SwapInterval::None });
+
+        gl::load_with(|symbol| window.get_proc_address(symbol) as *const _);
+        if !gl::Viewport::is_loaded() {
+            return Err(WindowError::LoadGl);
+        }
+        self.install_debug();
+        Ok((glfw, window, events))
+    }
+
+    // Installs debug output on the just created context when asked for.
+    pub(crate) fn install_debug(&self) {
+        if self.debug && !debug::install(self.debug_severity, self.debug_panic) {
+            log::warn!(target: "gl", "the context has no debug output (KHR_debug)");
+        }
+    }
+}
+
+// GLFW errors are logged and the last one kept for the WindowError, instead
+// of panicking in the callback.
+const RECORD_ERRORS: Option<glfw::ErrorCallback<()>> = Some(glfw::Callback {
+    f: record_error as fn(glfw::Error, String, &()),
+    data: ()
+});
+
+static LAST_ERROR: Mutex<Option<String>> = Mutex::new(None);
+
+fn record_error(error: glfw::Error, description: String, _: &()) {
+    log::error!(target: "glfw", "{:?}: {}", error, description);
+    *LAST_ERROR.lock().unwrap() = Some(description);
+}
+
+fn create_window_error() -> WindowError {
+    let description = LAST_ERROR.lock().unwrap().take();
+    WindowError::CreateWindow(description.unwrap_or_else(|| "no error reported".to_string()))
+}
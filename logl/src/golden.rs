@@ -0,0 +1,222 @@
+// Golden-image checks: every chapter is rendered headless at a fixed time and
+// compared against its reference in golden/. Failures leave the actual,
+// expected and diff images in golden/failures/ for a look. Chapters run in a
+// debug context and fail on their first GL error.
+use std::{fs, panic::{self, AssertUnwindSafe}, path::{Path, PathBuf}};
+
+use image::{Pixel, Rgba, RgbaImage};
+
+use common::{app::{self, Chapter, Settings}, clock::ClockMode, window::WindowBuilder};
+
+const DIR: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/golden");
+
+// small enough to keep the references cheap to check in
+const WIDTH: u32 = 400;
+const HEIGHT: u32 = 300;
+// every chapter is captured at this time
+const TIME: f64 = 1.;
+// The first frame runs the simulation up to TIME, the second one renders it
+// again so state chapters only set for the next frame (1.5-exercise sets
+// uniforms before use_program) has settled.
+const FRAMES: u64 = 2;
+
+// A pixel differs when any channel is off by more than this. Drivers round
+// filtering and blending a little differently, so exact matches are not
+// expected off llvmpipe.
+const PIXEL_THRESHOLD: u8 = 8;
+// fraction of pixels allowed over the threshold
+const MAX_DIFFERENT: f64 = 0.002;
+// mean structural similarity of the luma, 1 for identical images
+const MIN_SSIM: f64 = 0.98;
+
+const SSIM_WINDOW: u32 = 8;
+const SSIM_STRIDE: u32 = 4;
+
+pub struct Comparison {
+    pub different: f64,
+    pub ssim: f64,
+    pub diff: RgbaImage
+}
+
+impl Comparison {
+    pub fn passed(&self) -> bool {
+        self.different <= MAX_DIFFERENT && self.ssim >= MIN_SSIM
+    }
+}
+
+fn settings() -> Settings {
+    let window = WindowBuilder::new("golden", WIDTH, HEIGHT)
+        .srgb(true)
+        .debug(true)
+        .debug_panic(true);
+    let mut settings = Settings::new(window);
+    settings.clock = ClockMode::Scripted(vec![TIME]);
+    settings
+}
+
+fn reference_path(chapter: &Chapter) -> PathBuf {
+    Path::new(DIR).join(format!("{}.png", chapter.name))
+}
+
+// Checks (or with `bless`, rewrites) the references of `chapters`, returns
+// how many failed.
+pub fn check(chapters: &[&Chapter], bless: bool) -> usize {
+    let settings = settings();
+    let mut failed = 0;
+    for chapter in chapters {
+        let rendered = panic::catch_unwind(AssertUnwindSafe(|| app::run_headless(&settings, chapter, FRAMES)));
+        let actual = match rendered {
+            Ok(Ok(actual)) => actual,
+            Ok(Err(e)) => {
+                println!("{:<14} ERROR {}", chapter.name, e);
+                failed += 1;
+                continue;
+            }
+            Err(panic) => {
+                println!("{:<14} FAIL {}", chapter.name, panic_message(&*panic));
+                failed += 1;
+                continue;
+            }
+        };
+
+        let reference = reference_path(chapter);
+        if bless {
+            let saved = fs::create_dir_all(DIR)
+                .map_err(|e| e.to_string())
+                .and_then(|_| actual.save(&reference).map_err(|e| e.to_string()));
+            match saved {
+                Ok(()) => println!("{:<14} blessed", chapter.name),
+                Err(e) => {
+                    println!("{:<14} ERROR saving {}: {}", chapter.name, reference.display(), e);
+                    failed += 1;
+                }
+            }
+            continue;
+        }
+
+        let expected = match image::open(&reference) {
+            Ok(expected) => expected.to_rgba(),
+            Err(e) => {
+                println!("{:<14} FAIL no reference {}: {}", chapter.name, reference.display(), e);
+                failed += 1;
+                continue;
+            }
+        };
+        match compare(&actual, &expected) {
+            Some(comparison) if comparison.passed() => println!("{:<14} ok   {:.3}% different, ssim {:.4}",
+                chapter.name,
+                comparison.different * 100.,
+                comparison.ssim
+            ),
+            comparison => {
+                failed += 1;
+                match &comparison {
+                    Some(c) => println!("{:<14} FAIL {:.3}% different, ssim {:.4}", chapter.name, c.different * 100., c.ssim),
+                    None => println!("{:<14} FAIL size {:?}, expected {:?}", chapter.name, actual.dimensions(), expected.dimensions())
+                }
+                if let Err(e) = write_failure(chapter, &actual, &expected, comparison.as_ref()) {
+                    println!("{:<14} ERROR writing failure images: {}", chapter.name, e);
+                }
+            }
+        }
+    }
+    failed
+}
+
+fn panic_message(panic: &(dyn std::any::Any + Send)) -> &str {
+    match panic.downcast_ref::<&str>() {
+        Some(message) => message,
+        None => panic.downcast_ref::<String>().map_or("panicked", String::as_str)
+    }
+}
+
+fn write_failure(chapter: &Chapter, actual: &RgbaImage, expected: &RgbaImage, comparison: Option<&Comparison>) -> Result<(), String> {
+    let dir = Path::new(DIR).join("failures");
+    fs::create_dir_all(&dir).map_err(|e| e.to_string())?;
+    let save = |image: &RgbaImage, kind: &str| {
+        image.save(dir.join(format!("{}.{}.png", chapter.name, kind))).map_err(|e| e.to_string())
+    };
+    save(actual, "actual")?;
+    save(expected, "expected")?;
+    if let Some(comparison) = comparison {
+        save(&comparison.diff, "diff")?;
+    }
+    Ok(())
+}
+
+// None when the sizes differ.
+pub fn compare(actual: &RgbaImage, expected: &RgbaImage) -> Option<Comparison> {
+    if actual.dimensions() != expected.dimensions() {
+        return None;
+    }
+    let (width, height) = actual.dimensions();
+
+    // differing pixels in red over the amplified difference
+    let mut diff = RgbaImage::new(width, height);
+    let mut different = 0;
+    for (x, y, a) in actual.enumerate_pixels() {
+        let e = expected.get_pixel(x, y);
+        let delta = (0..4).map(|c| a.channels()[c].abs_diff(e.channels()[c])).max().unwrap();
+        let pixel = if delta > PIXEL_THRESHOLD {
+            different += 1;
+            Rgba::from_channels(255, 0, 0, 255)
+        } else {
+            let v = delta.saturating_mul(16);
+            Rgba::from_channels(v, v, v, 255)
+        };
+        diff.put_pixel(x, y, pixel);
+    }
+
+    Some(Comparison {
+        different: different as f64 / (width * height) as f64,
+        ssim: ssim(&luma(actual), &luma(expected), width, height),
+        diff
+    })
+}
+
+fn luma(image: &RgbaImage) -> Vec<f64> {
+    image
+        .pixels()
+        .map(|p| {
+            let c = p.channels();
+            0.299 * c[0] as f64 + 0.587 * c[1] as f64 + 0.114 * c[2] as f64
+        })
+        .collect()
+}
+
+// Mean SSIM over overlapping windows, with the usual constants for 8 bit
+// values.
+fn ssim(a: &[f64], b: &[f64], width: u32, height: u32) -> f64 {
+    const C1: f64 = (0.01 * 255.) * (0.01 * 255.);
+    const C2: f64 = (0.03 * 255.) * (0.03 * 255.);
+    if width < SSIM_WINDOW || height < SSIM_WINDOW {
+        return if a == b { 1. } else { 0. };
+    }
+
+    let n = (SSIM_WINDOW * SSIM_WINDOW) as f64;
+    let mut total = 0.;
+    let mut windows = 0;
+    for y0 in (0..=height - SSIM_WINDOW).step_by(SSIM_STRIDE as usize) {
+        for x0 in (0..=width - SSIM_WINDOW).step_by(SSIM_STRIDE as usize) {
+            let (mut sum_a, mut sum_b, mut sum_aa, mut sum_bb, mut sum_ab) = (0., 0., 0., 0., 0.);
+            for y in y0..y0 + SSIM_WINDOW {
+                for x in x0..x0 + SSIM_WINDOW {
+                    let i = (y * width + x) as usize;
+                    sum_a += a[i];
+                    sum_b += b[i];
+                    sum_aa += a[i] * a[i];
+                    sum_bb += b[i] * b[i];
+                    sum_ab += a[i] * b[i];
+                }
+            }
+            let (mean_a, mean_b) = (sum_a / n, sum_b / n);
+            let var_a = sum_aa / n - mean_a * mean_a;
+            let var_b = sum_bb / n - mean_b * mean_b;
+            let covariance = sum_ab / n - mean_a * mean_b;
+            total += ((2. * mean_a * mean_b + C1) * (2. * covariance + C2))
+                / ((mean_a * mean_a + mean_b * mean_b + C1) * (var_a + var_b + C2));
+            windows += 1;
+        }
+    }
+    total / windows as f64
+}
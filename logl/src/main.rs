@@ -0,0 +1,264 @@
+use std::{env, path::PathBuf, process};
+
+use common::{app::{self, Chapter, Settings}, clock::ClockMode, recorder::RecordFormat, window::WindowBuilder};
+
+#[cfg(feature = "headless")]
+mod golden;
+
+const SCREEN_WIDTH:     u32 = 800;
+const SCREEN_HEIGHT:    u32 = 600;
+
+const USAGE: &str = "usage: logl list
+       logl run <chapter> [--time <seconds>] [--supersample <n>]
+                [--record <seconds>] [--record-format gif|apng|png|mp4|webm]
+                [--record-fps <n>] [--codec <name>] [--crf <n>]
+                [--headless] [--frames <n>] [--out <file.png>] [--gl-debug]
+                [--profile] [--trace <file.json>]
+       logl golden [--bless] [<chapter>...]
+
+F1 opens the chapter menu while running, F12 saves a screenshot to
+screenshots/, F9 starts and stops a recording in recordings/, F3 shows
+CPU and GPU frame times in the title bar and F2 the chapter's debug UI.
+--time holds the animation clock at <seconds>.
+--supersample renders screenshots and --out at <n> times the size and
+scales them down.
+--record records the first <seconds> at 30 frames per second (or
+--record-fps), as a GIF (default), an APNG, a directory of PNGs or, through
+ffmpeg, an MP4 (libx264) or a WebM (libvpx-vp9). --codec and --crf pick
+ffmpeg's video codec and constant rate factor.
+--headless renders <n> frames (default 1) offscreen, each 1/60 s after the
+previous one, and saves the last one to <file.png> (default frame.png).
+--gl-debug creates a debug context and logs the driver's messages, set
+RUST_LOG=gl=debug to see all of them.
+--profile prints the min/avg/max CPU and GPU time of every profiled scope
+at exit, --trace writes them as a Chrome trace (chrome://tracing).
+golden compares headless renders of the chapters (default all) against
+logl/golden/, --bless replaces the references instead.
+Both need the headless feature.";
+
+struct RunOptions {
+    time: Option<f64>,
+    supersample: u32,
+    record: Option<f64>,
+    record_format: RecordFormat,
+    record_fps: f64,
+    headless: bool,
+    gl_debug: bool,
+    profile: bool,
+    trace: Option<String>,
+    frames: u64,
+    out: String
+}
+
+fn parse_run_options(args: &[&str]) -> Result<RunOptions, String> {
+    let mut options = RunOptions {
+        time: None,
+        supersample: 1,
+        record: None,
+        record_format: RecordFormat::Gif,
+        record_fps: 30.,
+        headless: false,
+        gl_debug: false,
+        profile: false,
+        trace: None,
+        frames: 1,
+        out: "frame.png".to_string()
+    };
+    let mut codec = None;
+    let mut crf = None;
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match *arg {
+            "--time" => {
+                let value = args.next().ok_or("--time needs a value")?;
+                options.time = Some(value.parse().map_err(|_| format!("bad time {:?}", value))?);
+            }
+            "--supersample" => {
+                let value = args.next().ok_or("--supersample needs a value")?;
+                options.supersample = value.parse().map_err(|_| format!("bad supersampling {:?}", value))?;
+            }
+            "--record" => {
+                let value = args.next().ok_or("--record needs a value")?;
+                options.record = Some(value.parse().map_err(|_| format!("bad recording length {:?}", value))?);
+            }
+            "--record-format" => {
+                let value = args.next().ok_or("--record-format needs a value")?;
+                options.record_format = RecordFormat::parse(value).ok_or_else(|| format!("bad recording format {:?}", value))?;
+            }
+            "--record-fps" => {
+                let value = args.next().ok_or("--record-fps needs a value")?;
+                options.record_fps = value.parse().map_err(|_| format!("bad recording rate {:?}", value))?;
+                if options.record_fps.is_nan() || options.record_fps <= 0. {
+                    return Err(format!("bad recording rate {:?}", value));
+                }
+            }
+            "--codec" => codec = Some(args.next().ok_or("--codec needs a value")?.to_string()),
+            "--crf" => {
+                let value = args.next().ok_or("--crf needs a value")?;
+                crf = Some(value.parse().map_err(|_| format!("bad crf {:?}", value))?);
+            }
+            "--headless" => options.headless = true,
+            "--gl-debug" => options.gl_debug = true,
+            "--profile" => options.profile = true,
+            "--trace" => options.trace = Some(args.next().ok_or("--trace needs a value")?.to_string()),
+            "--frames" => {
+                let value = args.next().ok_or("--frames needs a value")?;
+                options.frames = value.parse().map_err(|_| format!("bad frame count {:?}", value))?;
+            }
+            "--out" => options.out = args.next().ok_or("--out needs a value")?.to_string(),
+            _ => return Err(format!("unknown option {:?}", arg))
+        }
+    }
+    match &mut options.record_format {
+        RecordFormat::Ffmpeg(ffmpeg) => {
+            if let Some(codec) = codec {
+                ffmpeg.codec = codec;
+            }
+            if let Some(crf) = crf {
+                ffmpeg.crf = crf;
+            }
+        }
+        _ if codec.is_some() || crf.is_some() => return Err("--codec and --crf need --record-format mp4 or webm".to_string()),
+        _ => {}
+    }
+    Ok(options)
+}
+
+#[cfg(feature = "headless")]
+fn run_headless(settings: &Settings, chapter: &Chapter, options: &RunOptions) {
+    // the chapter runs from its own directory
+    let out = app::from_start_dir(options.out.as_ref());
+    match app::run_headless(settings, chapter, options.frames) {
+        Ok(image) => {
+            if let Err(e) = image.save(&out) {
+                eprintln!("failed to save {}: {}", out.display(), e);
+                process::exit(1);
+            }
+        }
+        Err(e) => {
+            eprintln!("{}", e);
+            process::exit(1);
+        }
+    }
+}
+
+#[cfg(not(feature = "headless"))]
+fn run_headless(_settings: &Settings, _chapter: &Chapter, _options: &RunOptions) {
+    no_headless();
+}
+
+#[cfg(feature = "headless")]
+fn check_golden(chapters: &[&Chapter], bless: bool) {
+    let failed = golden::check(chapters, bless);
+    if failed > 0 {
+        eprintln!("{} of {} chapters failed", failed, chapters.len());
+        process::exit(1);
+    }
+}
+
+#[cfg(not(feature = "headless"))]
+fn check_golden(_chapters: &[&Chapter], _bless: bool) {
+    no_headless();
+}
+
+#[cfg(not(feature = "headless"))]
+fn no_headless() -> ! {
+    eprintln!("logl was built without headless rendering, rebuild with --features headless");
+    process::exit(2);
+}
+
+fn chapters() -> Vec<Chapter> {
+    vec![
+        opengl_1_1::chapter(),
+        opengl_1_2::chapter(),
+        opengl_1_2_exercise::chapter(),
+        opengl_1_3::chapter(),
+        opengl_1_3_exercise::chapter(),
+        opengl_1_4::chapter(),
+        opengl_1_4_exercise::chapter(),
+        opengl_1_5::chapter(),
+        opengl_1_5_exercise::chapter(),
+        opengl_1_6::chapter(),
+        opengl_1_6_exercise::chapter()
+    ]
+}
+
+fn main() {
+    // warnings and errors unless RUST_LOG says otherwise
+    env_logger::Builder::from_env(env_logger::Env::default().filter_or("RUST_LOG", "warn")).init();
+
+    let args: Vec<String> = env::args().skip(1).collect();
+    let args: Vec<&str> = args.iter().map(String::as_str).collect();
+    let chapters = chapters();
+
+    match args.as_slice() {
+        ["list"] => {
+            for chapter in &chapters {
+                println!("{}", chapter.name);
+            }
+        }
+        ["run", name, rest @ ..] => {
+            let start = match chapters.iter().position(|c| c.name == *name) {
+                Some(start) => start,
+                None => {
+                    eprintln!("unknown chapter {:?}, see `logl list`", name);
+                    process::exit(2);
+                }
+            };
+            let options = match parse_run_options(rest) {
+                Ok(options) => options,
+                Err(e) => {
+                    eprintln!("{}\n{}", e, USAGE);
+                    process::exit(2);
+                }
+            };
+            // sRGB capable for every chapter, the ones that want gamma
+            // correction enable FRAMEBUFFER_SRGB themselves
+            let window = WindowBuilder::new("dev", SCREEN_WIDTH, SCREEN_HEIGHT)
+                .srgb(true)
+                .debug(options.gl_debug);
+            let mut settings = Settings::new(window);
+            settings.screenshot_scale = options.supersample;
+            settings.record_seconds = options.record;
+            settings.record_format = options.record_format.clone();
+            settings.record_fps = options.record_fps;
+            settings.profile = options.profile;
+            settings.trace = options.trace.as_ref().map(PathBuf::from);
+            settings.clock = match options.time {
+                Some(time) => ClockMode::Scripted(vec![time]),
+                None if options.headless => ClockMode::Fixed { step: settings.fixed_step },
+                None => ClockMode::RealTime
+            };
+            if options.headless {
+                run_headless(&settings, &chapters[start], &options);
+            } else if let Err(e) = app::run(settings, &chapters, start) {
+                eprintln!("{}", e);
+                process::exit(1);
+            }
+        }
+        ["golden", rest @ ..] => {
+            let bless = rest.contains(&"--bless");
+            let names: Vec<&str> = rest.iter().cloned().filter(|arg| *arg != "--bless").collect();
+            let selected: Vec<&Chapter> = if names.is_empty() {
+                chapters.iter().collect()
+            } else {
+                let mut selected = Vec::new();
+                for name in names {
+                    match chapters.iter().find(|c| c.name == name) {
+                        Some(chapter) => selected.push(chapter),
+                        None => {
+                            eprintln!("unknown chapter {:?}, see `logl list`", name);
+                            process::exit(2);
+                        }
+                    }
+                }
+                selected
+            };
+            check_golden(&selected, bless);
+        }
+        _ => {
+            eprintln!("{}", USAGE);
+            process::exit(2);
+        }
+    }
+}
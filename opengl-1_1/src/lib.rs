@@ -0,0 +1,34 @@
+use gl;
+use glfw::{Key, Action};
+use common::app::{App, Chapter, Context};
+
+pub struct HelloWindow;
+
+pub fn chapter() -> Chapter {
+    Chapter::new::<HelloWindow>("1.1", env!("CARGO_MANIFEST_DIR"))
+}
+
+impl App for HelloWindow {
+    fn init(_ctx: &mut Context) -> HelloWindow {
+        HelloWindow
+    }
+
+    fn on_event(&mut self, ctx: &mut Context, event: &glfw::WindowEvent) {
+        match *event {
+            glfw::WindowEvent::FramebufferSize(w, h) => {
+                unsafe {
+                    gl::Viewport(0, 0, w, h);
+                }
+            }
+            glfw::WindowEvent::Key(Key::Escape, _, Action::Press, _) => ctx.quit(),
+            _ => {}
+        }
+    }
+
+    fn render(&mut self, _ctx: &mut Context, _alpha: f32) {
+        unsafe {
+            gl::ClearColor(0.2, 0.3, 0.3, 1.0);
+            gl::Clear(gl::COLOR_BUFFER_BIT);
+        }
+    }
+}
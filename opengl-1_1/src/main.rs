@@ -1,53 +0,0 @@
-use gl;
-use glfw::{self, Context, Key, Action};
-use std::{sync::mpsc::Receiver};
-
-const SCREEN_WIDTH:     u32 = 800;
-const SCREEN_HEIGHT:    u32 = 600;
-
-fn main() {
-    let mut glfw = glfw::init(glfw::FAIL_ON_ERRORS).unwrap();
-    glfw.window_hint(glfw::WindowHint::ContextVersion(3, 3));
-    glfw.window_hint(glfw::WindowHint::OpenGlProfile(glfw::OpenGlProfileHint::Core));
-    #[cfg(target_os = "macos")]
-    glfw.window_hint(glfw::WindowHint::OpenGlForwardCompat(true));
-
-    let (mut window, events) = glfw.create_window(
-        SCREEN_WIDTH,
-        SCREEN_HEIGHT, 
-        "dev", 
-        glfw::WindowMode::Windowed
-    ).expect("Failed to crate GLFW window");
-    
-    window.make_current();
-    window.set_key_polling(true);
-    window.set_framebuffer_size_polling(true);
-
-    gl::load_with(|symbol| window.get_proc_address(symbol) as *const _);
-
-    while !window.should_close() {
-        process_events(&mut window, &events);
-
-        unsafe {
-            gl::ClearColor(0.2, 0.3, 0.3, 1.0);
-            gl::Clear(gl::COLOR_BUFFER_BIT);
-        }
-
-        window.swap_buffers();
-        glfw.poll_events();
-    }
-}
-
-fn process_events(window: &mut glfw::Window, events: &Receiver<(f64, glfw::WindowEvent)>) {
-    for (_, event) in glfw::flush_messages(events) {
-        match event {
-            glfw::WindowEvent::FramebufferSize(w, h) => {
-                unsafe {
-                    gl::Viewport(0, 0, w, h);
-                }
-            }
-            glfw::WindowEvent::Key(Key::Escape, _, Action::Press, _) => window.set_should_close(true),
-            _ => {}
-        }
-    }
-}
@@ -1,10 +1,8 @@
 #![allow(non_upper_case_globals)]
 use gl::{self, types::{GLchar, GLfloat, GLint, GLsizei, GLsizeiptr}};
-use glfw::{self, Context, Key, Action};
-use std::{ffi::{CString, c_void}, mem, ptr, sync::mpsc::Receiver, str};
-
-const SCREEN_WIDTH:     u32 = 800;
-const SCREEN_HEIGHT:    u32 = 600;
+use glfw::{Key, Action};
+use std::{ffi::{CString, c_void}, mem, ptr, str};
+use common::app::{App, Chapter, Context};
 
 const VERTEX_SHADER_SOURCE: &str = r#"
 #version 330 core
@@ -31,60 +29,66 @@ void main() {
 }
 "#;
 
-fn main() {
-    let mut glfw = glfw::init(glfw::FAIL_ON_ERRORS).unwrap();
-    glfw.window_hint(glfw::WindowHint::ContextVersion(3, 3));
-    glfw.window_hint(glfw::WindowHint::OpenGlProfile(glfw::OpenGlProfileHint::Core));
-    #[cfg(target_os = "macos")]
-    glfw.window_hint(glfw::WindowHint::OpenGlForwardCompat(true));
-
-    let (mut window, events) = glfw.create_window(
-        SCREEN_WIDTH,
-        SCREEN_HEIGHT, 
-        "dev", 
-        glfw::WindowMode::Windowed
-    ).expect("Failed to crate GLFW window");
-    
-    window.make_current();
-    window.set_key_polling(true);
-    window.set_framebuffer_size_polling(true);
-
-    gl::load_with(|symbol| window.get_proc_address(symbol) as *const _);
-
-    let triangles: ([f32; 9], [f32; 9]) = (
-        [
-            0.2,  0.0,  0.0,
-            0.5,  0.8,  0.0,
-            0.8,  0.0,  0.0,
-        ],
-        [            
-            -0.2,  0.0,  0.0,
-            -0.5,  0.8,  0.0,
-            -0.8,  0.0,  0.0
-        ]
-    );
-
-    // let vertices: [f32; 18] = [
-    //      0.2,  0.0,  0.0,
-    //      0.5,  0.8,  0.0,
-    //      0.8,  0.0,  0.0,
-    //     -0.2,  0.0,  0.0,
-    //     -0.5,  0.8,  0.0,
-    //     -0.8,  0.0,  0.0
-    // ];
-
-    let (shader_program, vaos) = {
-        let shader_program = build_shaders();
-
-        let vaos = build_vaos(&triangles);
-
-        // gl::PolygonMode(gl::FRONT_AND_BACK, gl::LINE);
-        (shader_program, vaos)
-    };
-
-    while !window.should_close() {
-        process_events(&mut window, &events);
+pub struct HelloTriangle {
+    shader_program: (u32, u32),
+    vaos: [u32; 2]
+}
+
+pub fn chapter() -> Chapter {
+    Chapter::new::<HelloTriangle>("1.2-exercise", env!("CARGO_MANIFEST_DIR"))
+}
+
+impl App for HelloTriangle {
+    fn init(_ctx: &mut Context) -> HelloTriangle {
+
+        let triangles: ([f32; 9], [f32; 9]) = (
+            [
+                0.2,  0.0,  0.0,
+                0.5,  0.8,  0.0,
+                0.8,  0.0,  0.0,
+            ],
+            [            
+                -0.2,  0.0,  0.0,
+                -0.5,  0.8,  0.0,
+                -0.8,  0.0,  0.0
+            ]
+        );
+
+        // let vertices: [f32; 18] = [
+        //      0.2,  0.0,  0.0,
+        //      0.5,  0.8,  0.0,
+        //      0.8,  0.0,  0.0,
+        //     -0.2,  0.0,  0.0,
+        //     -0.5,  0.8,  0.0,
+        //     -0.8,  0.0,  0.0
+        // ];
+
+        let (shader_program, vaos) = {
+            let shader_program = build_shaders();
+
+            let vaos = build_vaos(&triangles);
+
+            // gl::PolygonMode(gl::FRONT_AND_BACK, gl::LINE);
+            (shader_program, vaos)
+        };
+
+        HelloTriangle { shader_program, vaos }
+    }
+
+    fn on_event(&mut self, ctx: &mut Context, event: &glfw::WindowEvent) {
+        match *event {
+            glfw::WindowEvent::FramebufferSize(w, h) => {
+                unsafe {
+                    gl::Viewport(0, 0, w, h);
+                }
+            }
+            glfw::WindowEvent::Key(Key::Escape, _, Action::Press, _) => ctx.quit(),
+            _ => {}
+        }
+    }
 
+    fn render(&mut self, _ctx: &mut Context, _alpha: f32) {
+        let (shader_program, vaos) = (self.shader_program, self.vaos);
         unsafe {
             gl::ClearColor(0.2, 0.3, 0.3, 1.0);
             gl::Clear(gl::COLOR_BUFFER_BIT);
@@ -99,23 +103,6 @@ fn main() {
             
             // gl::DrawElements(gl::TRIANGLES, 6, gl::UNSIGNED_INT, ptr::null());
         }
-
-        window.swap_buffers();
-        glfw.poll_events();
-    }
-}
-
-fn process_events(window: &mut glfw::Window, events: &Receiver<(f64, glfw::WindowEvent)>) {
-    for (_, event) in glfw::flush_messages(events) {
-        match event {
-            glfw::WindowEvent::FramebufferSize(w, h) => {
-                unsafe {
-                    gl::Viewport(0, 0, w, h);
-                }
-            }
-            glfw::WindowEvent::Key(Key::Escape, _, Action::Press, _) => window.set_should_close(true),
-            _ => {}
-        }
     }
 }
 
@@ -200,4 +187,4 @@ fn build_vaos(triangles: &([f32; 9], [f32; 9])) -> [u32; 2] {
 
         vaos
     }
-}
\ No newline at end of file
+}
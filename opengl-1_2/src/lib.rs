@@ -0,0 +1,173 @@
+#![allow(non_upper_case_globals)]
+use gl::{self, types::{GLchar, GLfloat, GLint, GLsizei, GLsizeiptr}};
+use glfw::{Key, Action};
+use std::{ffi::{CString, c_void}, mem, ptr, str};
+use common::app::{App, Chapter, Context};
+
+const VERTEX_SHADER_SOURCE: &str = r#"
+#version 330 core
+layout (location = 0) in vec3 aPos;
+
+void main() {
+    gl_Position = vec4(aPos.x, aPos.y, aPos.z, 1.0);
+}
+"#;
+const FRAGMENT_SHADER_SOURCE: &str = r#"
+#version 330 core
+out vec4 FragColor;
+
+void main() {
+    FragColor = vec4(1.0f, 0.5f, 0.2f, 1.0f);
+}
+"#;
+
+pub struct HelloTriangle {
+    shader_program: u32,
+    vao: u32
+}
+
+pub fn chapter() -> Chapter {
+    Chapter::new::<HelloTriangle>("1.2", env!("CARGO_MANIFEST_DIR"))
+}
+
+impl App for HelloTriangle {
+    fn init(_ctx: &mut Context) -> HelloTriangle {
+
+        let vertices: [f32; 18] = [
+             0.2,  0.0,  0.0,
+             0.5,  0.8,  0.0,
+             0.8,  0.0,  0.0,
+            -0.2,  0.0,  0.0,
+            -0.5,  0.8,  0.0,
+            -0.8,  0.0,  0.0
+        ];
+        let indicies = [
+            0, 1, 3,
+            1, 2, 3
+        ];
+
+        let (shader_program, vao) = unsafe {
+            let vertex_shader = gl::CreateShader(gl::VERTEX_SHADER);
+            let c_str_vert = CString::new(VERTEX_SHADER_SOURCE.as_bytes()).unwrap();
+            gl::ShaderSource(vertex_shader, 1, &c_str_vert.as_ptr(), ptr::null());
+            gl::CompileShader(vertex_shader);
+
+            let mut success = gl::FALSE as GLint;
+            let mut info_log = Vec::with_capacity(512);
+            info_log.set_len(512-1);
+            gl::GetShaderiv(vertex_shader, gl::COMPILE_STATUS, &mut success);
+            if success != gl::TRUE as GLint {
+                gl::GetShaderInfoLog(
+                    vertex_shader, 
+                    512, 
+                    ptr::null_mut(),
+                    info_log.as_mut_ptr() as *mut GLchar 
+                );
+                println!("ERROR::SHADER::VERTEX::COMPILATION_FAILED {}", str::from_utf8(&info_log).unwrap());
+            }
+
+            let fragment_shader = gl::CreateShader(gl::FRAGMENT_SHADER);
+            let c_str_frag = CString::new(FRAGMENT_SHADER_SOURCE.as_bytes()).unwrap();
+            gl::ShaderSource(fragment_shader, 1, &c_str_frag.as_ptr(), ptr::null());
+            gl::CompileShader(fragment_shader);
+
+            gl::GetShaderiv(fragment_shader, gl::COMPILE_STATUS, &mut success);
+            if success != gl::TRUE as GLint {
+                gl::GetShaderInfoLog(
+                    fragment_shader,
+                    512,
+                    ptr::null_mut(),
+                    info_log.as_mut_ptr() as *mut GLchar
+                );
+                println!("ERROR::SHADER::FRAGMENT::COMPILATION_FAILED {}", str::from_utf8(&info_log).unwrap());
+            }
+
+            let shader_program = gl::CreateProgram();
+            gl::AttachShader(shader_program, vertex_shader);
+            gl::AttachShader(shader_program, fragment_shader);
+            gl::LinkProgram(shader_program);
+            gl::GetProgramiv(shader_program, gl::LINK_STATUS, &mut success);
+            if success != gl::TRUE as GLint {
+                gl::GetProgramInfoLog(
+                    shader_program, 
+                    512, 
+                    ptr::null_mut(), 
+                    info_log.as_mut_ptr() as *mut GLchar
+                );
+                println!("ERROR::SHADER::PROGRAM::LINK_FAILED {}", str::from_utf8(&info_log).unwrap());
+            }
+
+            gl::DeleteShader(vertex_shader);
+            gl::DeleteShader(fragment_shader);
+
+            let (mut vbo, mut vao, mut ebo) = (0, 0, 0);
+            gl::GenVertexArrays(1, &mut vao);
+            gl::GenBuffers(1, &mut vbo);
+            gl::GenBuffers(1, &mut ebo);
+
+            gl::BindVertexArray(vao);
+
+            gl::BindBuffer(gl::ARRAY_BUFFER, vbo);
+            gl::BufferData(
+                gl::ARRAY_BUFFER, 
+                mem::size_of_val(&vertices) as GLsizeiptr, 
+                &vertices[0] as *const f32 as *const c_void, 
+                gl::STATIC_DRAW
+            );
+
+            gl::BindBuffer(gl::ELEMENT_ARRAY_BUFFER, ebo);
+            gl::BufferData(
+                gl::ELEMENT_ARRAY_BUFFER, 
+                mem::size_of_val(&indicies) as GLsizeiptr, 
+                &indicies[0] as *const i32 as *const c_void, 
+                gl::STATIC_DRAW
+            );
+
+
+            gl::VertexAttribPointer(
+                0,
+                3,
+                gl::FLOAT,
+                gl::FALSE,
+                3 * mem::size_of::<GLfloat>() as GLsizei,
+                ptr::null()
+            );
+            gl::EnableVertexAttribArray(0);
+
+            // unbind VBO and VAO
+            gl::BindBuffer(gl::ARRAY_BUFFER, 0);
+            gl::BindVertexArray(0);
+
+            // gl::PolygonMode(gl::FRONT_AND_BACK, gl::LINE);
+            (shader_program, vao)
+        };
+
+        HelloTriangle { shader_program, vao }
+    }
+
+    fn on_event(&mut self, ctx: &mut Context, event: &glfw::WindowEvent) {
+        match *event {
+            glfw::WindowEvent::FramebufferSize(w, h) => {
+                unsafe {
+                    gl::Viewport(0, 0, w, h);
+                }
+            }
+            glfw::WindowEvent::Key(Key::Escape, _, Action::Press, _) => ctx.quit(),
+            _ => {}
+        }
+    }
+
+    fn render(&mut self, _ctx: &mut Context, _alpha: f32) {
+        let (shader_program, vao) = (self.shader_program, self.vao);
+        unsafe {
+            gl::ClearColor(0.2, 0.3, 0.3, 1.0);
+            gl::Clear(gl::COLOR_BUFFER_BIT);
+            
+            gl::UseProgram(shader_program);
+            gl::BindVertexArray(vao);
+            
+            gl::DrawArrays(gl::TRIANGLES, 0, 6);
+            // gl::DrawElements(gl::TRIANGLES, 6, gl::UNSIGNED_INT, ptr::null());
+        }
+    }
+}
@@ -1,5 +1,7 @@
 use std::{ffi::CString, fs::File, io::Read, ptr};
 
+use common::uniforms;
+
 pub struct Shader {
     pub id: u32
 }
@@ -47,6 +49,7 @@ impl Shader {
             id
         };
 
+        uniforms::register(id, vertex_path, fragment_path);
         Shader { id }
     }
 
@@ -59,6 +62,7 @@ impl Shader {
             gl::GetUniformLocation(self.id, self.str2cstr(name).as_ptr()),
             val as i32
         );
+        uniforms::reapply(self.id, name);
     }
 
     pub unsafe fn set_int(&self, name: &str, val: i32) {
@@ -66,6 +70,7 @@ impl Shader {
             gl::GetUniformLocation(self.id, self.str2cstr(name).as_ptr()),
             val
         );
+        uniforms::reapply(self.id, name);
     }
 
     pub unsafe fn set_float(&self, name: &str, val: f32) {
@@ -73,6 +78,7 @@ impl Shader {
             gl::GetUniformLocation(self.id, self.str2cstr(name).as_ptr()),
             val
         );
+        uniforms::reapply(self.id, name);
     }
 
     unsafe fn str2cstr(&self, v: &str) -> CString {
@@ -1,5 +1,6 @@
 use std::{ffi::CString, fs::File, io::Read, ptr};
 
+use common::uniforms;
 use gl::types::{GLchar, GLint};
 
 pub struct Shader {
@@ -53,6 +54,7 @@ impl Shader {
             
             shader.id = id;
         };
+        uniforms::register(shader.id, vertex_path, fragment_path);
         
         shader
     }
@@ -66,6 +68,7 @@ impl Shader {
             gl::GetUniformLocation(self.id, self.str2cstr(name).as_ptr()),
             val as i32
         );
+        uniforms::reapply(self.id, name);
     }
 
     pub unsafe fn set_int(&self, name: &str, val: i32) {
@@ -73,6 +76,7 @@ impl Shader {
             gl::GetUniformLocation(self.id, self.str2cstr(name).as_ptr()),
             val
         );
+        uniforms::reapply(self.id, name);
     }
 
     pub unsafe fn set_float(&self, name: &str, val: f32) {
@@ -80,6 +84,7 @@ impl Shader {
             gl::GetUniformLocation(self.id, self.str2cstr(name).as_ptr()),
             val
         );
+        uniforms::reapply(self.id, name);
     }
 
     unsafe fn str2cstr(&self, v: &str) -> CString {
@@ -1,6 +1,7 @@
 use std::{ffi::CString, fs::File, io::Read, ptr};
 
 use cgmath::{Array, Matrix, Matrix4};
+use common::uniforms;
 use gl::types::{GLchar, GLint};
 
 pub struct Shader {
@@ -54,6 +55,7 @@ impl Shader {
             
             shader.id = id;
         };
+        uniforms::register(shader.id, vertex_path, fragment_path);
         
         shader
     }
@@ -67,6 +69,7 @@ impl Shader {
             gl::GetUniformLocation(self.id, self.str2cstr(name).as_ptr()),
             val as i32
         );
+        uniforms::reapply(self.id, name);
     }
 
     pub unsafe fn set_int(&self, name: &str, val: i32) {
@@ -74,6 +77,7 @@ impl Shader {
             gl::GetUniformLocation(self.id, self.str2cstr(name).as_ptr()),
             val
         );
+        uniforms::reapply(self.id, name);
     }
 
     pub unsafe fn set_float(&self, name: &str, val: f32) {
@@ -81,6 +85,7 @@ impl Shader {
             gl::GetUniformLocation(self.id, self.str2cstr(name).as_ptr()),
             val
         );
+        uniforms::reapply(self.id, name);
     }
 
     pub unsafe fn set_mat4f(&self, name: &str, val: &Matrix4<f32>){
@@ -89,6 +94,7 @@ impl Shader {
         gl::FALSE,
         val.as_ptr()
         );
+        uniforms::reapply(self.id, name);
     }
     
     unsafe fn str2cstr(&self, v: &str) -> CString {
@@ -0,0 +1,306 @@
+#![allow(non_upper_case_globals)]
+use cgmath::{Deg, Matrix4, Quaternion, perspective, vec3};
+use cgmath::prelude::*;
+use gl::{self, types::{GLfloat, GLsizei, GLsizeiptr}};
+use glfw::{Key, Action};
+use image::GenericImage;
+use std::{ffi::{c_void}, mem, path::Path, ptr};
+use common::{app::{App, Chapter, Context}, egui, ui::RenderState};
+use common::scene::{Material, Mesh, Scene, Transform};
+
+pub mod shader;
+use shader::Shader;
+
+pub struct CoordinateSystems {
+    shader: Shader,
+    scene: Scene,
+    percent: f32,
+    render_state: RenderState
+}
+
+pub fn chapter() -> Chapter {
+    Chapter::new::<CoordinateSystems>("1.6-exercise", env!("CARGO_MANIFEST_DIR"))
+}
+
+impl App for CoordinateSystems {
+    fn init(_ctx: &mut Context) -> CoordinateSystems {
+        let vertices = vec![
+            // coords          // tex coords 
+            -0.5, -0.5, -0.5,  0.0, 0.0,
+             0.5, -0.5, -0.5,  1.0, 0.0,
+             0.5,  0.5, -0.5,  1.0, 1.0,
+             0.5,  0.5, -0.5,  1.0, 1.0,
+            -0.5,  0.5, -0.5,  0.0, 1.0,
+            -0.5, -0.5, -0.5,  0.0, 0.0,
+    
+            -0.5, -0.5,  0.5,  0.0, 0.0,
+             0.5, -0.5,  0.5,  1.0, 0.0,
+             0.5,  0.5,  0.5,  1.0, 1.0,
+             0.5,  0.5,  0.5,  1.0, 1.0,
+            -0.5,  0.5,  0.5,  0.0, 1.0,
+            -0.5, -0.5,  0.5,  0.0, 0.0,
+    
+            -0.5,  0.5,  0.5,  1.0, 0.0,
+            -0.5,  0.5, -0.5,  1.0, 1.0,
+            -0.5, -0.5, -0.5,  0.0, 1.0,
+            -0.5, -0.5, -0.5,  0.0, 1.0,
+            -0.5, -0.5,  0.5,  0.0, 0.0,
+            -0.5,  0.5,  0.5,  1.0, 0.0,
+    
+             0.5,  0.5,  0.5,  1.0, 0.0,
+             0.5,  0.5, -0.5,  1.0, 1.0,
+             0.5, -0.5, -0.5,  0.0, 1.0,
+             0.5, -0.5, -0.5,  0.0, 1.0,
+             0.5, -0.5,  0.5,  0.0, 0.0,
+             0.5,  0.5,  0.5,  1.0, 0.0,
+    
+            -0.5, -0.5, -0.5,  0.0, 1.0,
+             0.5, -0.5, -0.5,  1.0, 1.0,
+             0.5, -0.5,  0.5,  1.0, 0.0,
+             0.5, -0.5,  0.5,  1.0, 0.0,
+            -0.5, -0.5,  0.5,  0.0, 0.0,
+            -0.5, -0.5, -0.5,  0.0, 1.0,
+    
+            -0.5,  0.5, -0.5,  0.0, 1.0,
+             0.5,  0.5, -0.5,  1.0, 1.0,
+             0.5,  0.5,  0.5,  1.0, 0.0,
+             0.5,  0.5,  0.5,  1.0, 0.0,
+            -0.5,  0.5,  0.5,  0.0, 0.0,
+            -0.5,  0.5, -0.5,  0.0, 1.0
+        ];
+
+        let indices = vec![
+            0, 1, 3,
+            1, 2, 3,
+        ];
+
+        let cube_positions = vec![
+            vec3( 0.0,  0.0,  0.0), 
+            vec3( 2.0,  5.0, -15.0), 
+            vec3(-1.5, -2.2, -2.5),  
+            vec3(-3.8, -2.0, -12.3),  
+            vec3( 2.4, -0.4, -3.5),  
+            vec3(-1.7,  3.0, -7.5),  
+            vec3( 1.3, -2.0, -2.5),  
+            vec3( 1.5,  2.0, -2.5), 
+            vec3( 1.5,  0.2, -1.5), 
+            vec3(-1.3,  1.0, -1.5)  
+        ];
+
+        let (shader, vao, texture) = {
+            let shader = Shader::new(
+                "shaders/vertex.glsl",
+                "shaders/fragment.glsl"
+            );
+
+            let vao = build_vaos(&vertices, &indices);
+
+            let texture = unsafe {
+                let (mut tex1, mut tex2) = (0, 0);
+
+                // first texture
+                gl::GenTextures(1, &mut tex1);
+                gl::BindTexture(gl::TEXTURE_2D, tex1);
+                // wrapping
+                gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_WRAP_S, gl::REPEAT as i32);
+                gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_WRAP_T, gl::REPEAT as i32);
+                // filtering
+                gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_MIN_FILTER, gl::LINEAR as i32);
+                gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_MAG_FILTER, gl::LINEAR as i32);
+                let img = image::open(&Path::new("assets/textures/container.jpg"))
+                    .expect("failed to load texture");
+                let data = img.raw_pixels();
+                gl::TexImage2D(
+                    gl::TEXTURE_2D,
+                    0,
+                    gl::RGB as i32,
+                    img.width() as i32,
+                    img.height() as i32,
+                    0,
+                    gl::RGB,
+                    gl::UNSIGNED_BYTE,
+                    &data[0] as *const u8 as *const c_void
+                );
+                gl::GenerateMipmap(gl::TEXTURE_2D);
+            
+                // second texture
+                gl::GenTextures(1, &mut tex2);
+                gl::BindTexture(gl::TEXTURE_2D, tex2);
+                // wrapping
+                gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_WRAP_S, gl::REPEAT as i32);
+                gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_WRAP_T, gl::REPEAT as i32);
+                // filtering
+                gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_MIN_FILTER, gl::LINEAR as i32);
+                gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_MAG_FILTER, gl::LINEAR as i32);
+                let img = image::open(&Path::new("assets/textures/awesomeface.png"))
+                    .expect("failed to load texture");
+                let img = img.flipv();
+                let data = img.raw_pixels();
+                gl::TexImage2D(
+                    gl::TEXTURE_2D,
+                    0,
+                    gl::RGB as i32,
+                    img.width() as i32,
+                    img.height() as i32,
+                    0,
+                    gl::RGBA,
+                    gl::UNSIGNED_BYTE,
+                    &data[0] as *const u8 as *const c_void
+                );
+                gl::GenerateMipmap(gl::TEXTURE_2D);
+            
+                shader.use_program();
+                shader.set_int("texture1", 0);
+                shader.set_int("texture2", 1);
+
+                (tex1, tex2)
+            };
+
+            unsafe {
+                // gl::PolygonMode(gl::FRONT_AND_BACK, gl::LINE);
+                gl::Enable(gl::DEPTH_TEST);
+            }
+            (shader, vao, texture)
+        };
+
+        let mut scene = Scene::new();
+        for (i, pos) in cube_positions.iter().enumerate() {
+            let cube = scene.add(&format!("cube{}", i), Transform {
+                rotation: Quaternion::from_axis_angle(vec3(1.0, 0.3, 0.5).normalize(), Deg(20. * i as f32)),
+                ..Transform::from_translation(*pos)
+            }, None);
+            let node = scene.node_mut(cube);
+            node.mesh = Some(Mesh { vao, vertex_count: 36, bounds: None });
+            node.material = Some(Material { textures: vec![texture.0, texture.1] });
+            if i % 3 == 0 {
+                node.behavior = Some(Box::new(|local: &mut Transform, time: f32, _dt: f32| {
+                    local.rotation = Quaternion::from_axis_angle(vec3(0., 1., 0.), Deg(time * 50.));
+                }));
+            }
+        }
+
+        scene.update_world();
+
+        CoordinateSystems {
+            shader,
+            scene,
+            percent: 0.2,
+            render_state: RenderState::new([0.2, 0.3, 0.3, 1.0], true)
+        }
+    }
+
+    fn on_event(&mut self, ctx: &mut Context, event: &glfw::WindowEvent) {
+        match *event {
+            glfw::WindowEvent::FramebufferSize(w, h) => {
+                unsafe {
+                    gl::Viewport(0, 0, w, h);
+                }
+            }
+            glfw::WindowEvent::Key(Key::Escape, _, Action::Press, _) => ctx.quit(),
+            glfw::WindowEvent::Key(Key::Up, _, Action::Press, _) => self.percent = self.percent.clamp(0.0, 0.9) + 0.1,
+            glfw::WindowEvent::Key(Key::Down, _, Action::Press, _) => self.percent = self.percent.clamp(0.1, 1.0) - 0.1,
+            _ => {}
+        }
+    }
+
+    fn ui(&mut self, _ctx: &mut Context, ui: &egui::Context) {
+        egui::Window::new("1.6-exercise").show(ui, |ui| {
+            ui.add(egui::Slider::new(&mut self.percent, 0.0..=1.0).text("percent"));
+            self.render_state.ui(ui);
+            cubes_ui(ui, &mut self.scene);
+        });
+    }
+
+    fn fixed_update(&mut self, ctx: &mut Context, step: f32) {
+        self.scene.update((ctx.time + step as f64) as f32, step);
+    }
+
+    fn render(&mut self, ctx: &mut Context, _alpha: f32) {
+        unsafe {
+            self.render_state.apply();
+            gl::Clear(gl::COLOR_BUFFER_BIT | gl::DEPTH_BUFFER_BIT);
+
+            // transformations
+            let view: Matrix4<f32> = Matrix4::from_translation(vec3(0., 0., -4.));
+            let proj: Matrix4<f32> = perspective(
+                Deg(45.0), 
+                (ctx.size(). 0/ctx.size().1) as f32, 
+                0.1, 
+                100.0
+            );
+            self.shader.set_mat4f("view",  &view);
+            self.shader.set_mat4f("proj",  &proj);
+            
+            self.shader.use_program();
+            self.shader.set_float("percent", self.percent);
+            let shader = &self.shader;
+            self.scene.draw(|_, model| shader.set_mat4f("model", model));
+            // gl::DrawElements(gl::TRIANGLES, 6, gl::UNSIGNED_INT, ptr::null());      
+        }
+    }
+}
+
+// A collapsible transform editor per cube, picked up by the next update.
+fn cubes_ui(ui: &mut egui::Ui, scene: &mut Scene) {
+    ui.collapsing("cubes", |ui| {
+        for id in 0..scene.len() {
+            let node = scene.node(id);
+            let mut local = *node.local();
+            let edited = ui.collapsing(node.name.as_str(), |ui| local.ui(ui)).body_returned == Some(true);
+            if edited {
+                *scene.transform_mut(id) = local;
+            }
+        }
+    });
+}
+
+fn build_vaos(vertices: &Vec<f32>, indices: &Vec<i32>) -> u32 {
+    unsafe {
+        let (mut vbo, mut vao, mut ebo) = (0, 0, 0);
+        gl::GenVertexArrays(1, &mut vao);
+        gl::GenBuffers(1, &mut vbo);
+        gl::GenBuffers(1, &mut ebo);
+
+        // first triangle
+        gl::BindVertexArray(vao);
+        gl::BindBuffer(gl::ARRAY_BUFFER, vbo);
+        gl::BufferData(
+            gl::ARRAY_BUFFER, 
+            (vertices.len() * mem::size_of::<GLfloat>()) as GLsizeiptr, 
+            &vertices[0] as *const f32 as *const c_void, 
+            gl::STATIC_DRAW
+        );
+        gl::BindBuffer(gl::ELEMENT_ARRAY_BUFFER, ebo);
+        gl::BufferData(
+            gl::ELEMENT_ARRAY_BUFFER, 
+            (indices.len() * mem::size_of::<GLfloat>()) as GLsizeiptr, 
+            &indices[0] as *const i32 as *const c_void, 
+            gl::STATIC_DRAW
+        );
+
+        let stride = 5 * mem::size_of::<GLfloat>() as GLsizei;
+        // aPos
+        gl::VertexAttribPointer(
+            0,
+            3,
+            gl::FLOAT,
+            gl::FALSE,
+            stride,
+            ptr::null()
+        );
+        gl::EnableVertexAttribArray(0);
+        
+        // aTexCoord
+        gl::VertexAttribPointer(
+            1,
+            2,
+            gl::FLOAT,
+            gl::FALSE,
+            stride,
+            (3 * mem::size_of::<GLfloat>()) as *const c_void
+        );
+        gl::EnableVertexAttribArray(1);
+
+        vao
+    }
+}
\ No newline at end of file
@@ -0,0 +1,344 @@
+use common::debug;
+use image::{DynamicImage, GenericImage, RgbaImage};
+use serde::Serialize;
+
+use crate::texture::{self, ColorSpace};
+
+#[derive(Clone, Debug, Serialize)]
+pub struct AtlasRect {
+    pub name: String,
+    pub page: usize,
+    // pixel rect of the image itself, padding and extrusion excluded
+    pub x: u32,
+    pub y: u32,
+    pub width: u32,
+    pub height: u32,
+    // [u0, v0, u1, v1] with the origin in the top-left corner of the page
+    pub uv: [f32; 4]
+}
+
+#[derive(Serialize)]
+pub struct AtlasLayout<'a> {
+    pub page_width: u32,
+    pub page_height: u32,
+    pub pages: usize,
+    pub rects: &'a [AtlasRect]
+}
+
+pub struct Atlas {
+    pub page_width: u32,
+    pub page_height: u32,
+    pub pages: Vec<RgbaImage>,
+    // in the order the images were added to the builder
+    pub rects: Vec<AtlasRect>
+}
+
+impl Atlas {
+    pub fn rect(&self, name: &str) -> Option<&AtlasRect> {
+        self.rects.iter().find(|r| r.name == name)
+    }
+
+    pub fn layout_json(&self) -> String {
+        let layout = AtlasLayout {
+            page_width: self.page_width,
+            page_height: self.page_height,
+            pages: self.pages.len(),
+            rects: &self.rects
+        };
+        serde_json::to_string_pretty(&layout).unwrap()
+    }
+
+    /// # Safety
+    ///
+    /// Needs a current GL context.
+    pub unsafe fn upload(&self, space: ColorSpace) -> Vec<u32> {
+        self.pages
+            .iter()
+            .enumerate()
+            .map(|(i, page)| {
+                let id = texture::create(page.width() as i32, page.height() as i32, space, gl::RGBA, page);
+                debug::label(gl::TEXTURE, id, &format!("atlas page {}", i));
+                id
+            })
+            .collect()
+    }
+}
+
+pub struct AtlasBuilder {
+    pub page_width: u32,
+    pub page_height: u32,
+    // empty pixels kept around every image
+    pub padding: u32,
+    // fill the padding with the image's edge pixels instead of leaving it transparent
+    pub extrude: bool,
+    images: Vec<(String, RgbaImage)>
+}
+
+impl AtlasBuilder {
+    pub fn new(page_width: u32, page_height: u32) -> AtlasBuilder {
+        AtlasBuilder {
+            page_width,
+            page_height,
+            padding: 1,
+            extrude: true,
+            images: Vec::new()
+        }
+    }
+
+    pub fn add(&mut self, name: &str, img: &DynamicImage) -> &mut AtlasBuilder {
+        self.images.push((name.to_string(), img.to_rgba()));
+        self
+    }
+
+    pub fn build(&self) -> Result<Atlas, String> {
+        let cells: Vec<(u32, u32)> = self.images
+            .iter()
+            .map(|(_, img)| (img.width() + 2 * self.padding, img.height() + 2 * self.padding))
+            .collect();
+        let placements = pack(self.page_width, self.page_height, &cells)
+            .map_err(|i| format!(
+                "{} ({}x{} with padding) does not fit into a {}x{} atlas page",
+                self.images[i].0, cells[i].0, cells[i].1, self.page_width, self.page_height
+            ))?;
+
+        let page_count = placements.iter().map(|p| p.page + 1).max().unwrap_or(0);
+        let mut pages: Vec<RgbaImage> = (0..page_count)
+            .map(|_| RgbaImage::new(self.page_width, self.page_height))
+            .collect();
+
+        let (pw, ph) = (self.page_width as f32, self.page_height as f32);
+        let mut rects = Vec::with_capacity(self.images.len());
+        for ((name, img), placement) in self.images.iter().zip(&placements) {
+            let (x, y) = (placement.x + self.padding, placement.y + self.padding);
+            blit(&mut pages[placement.page], img, x, y, if self.extrude { self.padding } else { 0 });
+            rects.push(AtlasRect {
+                name: name.clone(),
+                page: placement.page,
+                x,
+                y,
+                width: img.width(),
+                height: img.height(),
+                uv: [
+                    x as f32 / pw,
+                    y as f32 / ph,
+                    (x + img.width()) as f32 / pw,
+                    (y + img.height()) as f32 / ph
+                ]
+            });
+        }
+
+        Ok(Atlas {
+            page_width: self.page_width,
+            page_height: self.page_height,
+            pages,
+            rects
+        })
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Placement {
+    pub page: usize,
+    pub x: u32,
+    pub y: u32
+}
+
+#[derive(Clone, Copy)]
+struct Segment {
+    x: u32,
+    y: u32,
+    width: u32
+}
+
+// Bottom-left skyline packer, one skyline per page. Cells are placed tallest
+// first, ties broken by width and then by input order, so the same input
+// always produces the same layout. On failure returns the index of the cell
+// that is larger than a page.
+pub fn pack(page_width: u32, page_height: u32, cells: &[(u32, u32)]) -> Result<Vec<Placement>, usize> {
+    let mut order: Vec<usize> = (0..cells.len()).collect();
+    order.sort_by(|&a, &b| cells[b].1.cmp(&cells[a].1)
+        .then(cells[b].0.cmp(&cells[a].0))
+        .then(a.cmp(&b))
+    );
+
+    let mut skylines: Vec<Vec<Segment>> = Vec::new();
+    let mut placements = vec![Placement { page: 0, x: 0, y: 0 }; cells.len()];
+    for i in order {
+        let (w, h) = cells[i];
+        if w > page_width || h > page_height {
+            return Err(i);
+        }
+
+        let mut placed = None;
+        for (page, skyline) in skylines.iter_mut().enumerate() {
+            if let Some((x, y)) = skyline_insert(skyline, page_width, page_height, w, h) {
+                placed = Some(Placement { page, x, y });
+                break;
+            }
+        }
+        placements[i] = match placed {
+            Some(placement) => placement,
+            None => {
+                let mut skyline = vec![Segment { x: 0, y: 0, width: page_width }];
+                let (x, y) = skyline_insert(&mut skyline, page_width, page_height, w, h).unwrap();
+                skylines.push(skyline);
+                Placement { page: skylines.len() - 1, x, y }
+            }
+        };
+    }
+    Ok(placements)
+}
+
+fn skyline_insert(skyline: &mut Vec<Segment>, page_width: u32, page_height: u32, w: u32, h: u32) -> Option<(u32, u32)> {
+    // (top edge, x, segment index) of the lowest, then leftmost fit
+    let mut best: Option<(u32, u32, usize)> = None;
+    for i in 0..skyline.len() {
+        let x = skyline[i].x;
+        if x + w > page_width {
+            break;
+        }
+        let mut y = 0;
+        let mut covered = 0;
+        for segment in &skyline[i..] {
+            if covered >= w {
+                break;
+            }
+            y = y.max(segment.y);
+            covered += segment.width;
+        }
+        if y + h > page_height {
+            continue;
+        }
+        let lower = match best {
+            Some((top, bx, _)) => (y + h, x) < (top, bx),
+            None => true
+        };
+        if lower {
+            best = Some((y + h, x, i));
+        }
+    }
+
+    let (top, x, i) = best?;
+    skyline.insert(i, Segment { x, y: top, width: w });
+
+    // shrink or drop the segments now hidden under the new one
+    let right = x + w;
+    while i + 1 < skyline.len() && skyline[i + 1].x < right {
+        let next = &mut skyline[i + 1];
+        let end = next.x + next.width;
+        if end <= right {
+            skyline.remove(i + 1);
+        } else {
+            next.width = end - right;
+            next.x = right;
+        }
+    }
+
+    // merge neighbours at the same height
+    let mut j = 0;
+    while j + 1 < skyline.len() {
+        if skyline[j].y == skyline[j + 1].y {
+            skyline[j].width += skyline[j + 1].width;
+            skyline.remove(j + 1);
+        } else {
+            j += 1;
+        }
+    }
+
+    Some((x, top - h))
+}
+
+// Copies img to (x, y) and repeats its border pixels `extrude` times outwards
+// so that linear filtering at the edges does not pick up the neighbours.
+fn blit(page: &mut RgbaImage, img: &RgbaImage, x: u32, y: u32, extrude: u32) {
+    let (w, h) = (img.width() as i64, img.height() as i64);
+    if w == 0 || h == 0 {
+        return;
+    }
+    let e = extrude as i64;
+    for dy in -e..h + e {
+        for dx in -e..w + e {
+            let src = *img.get_pixel(
+                dx.max(0).min(w - 1) as u32,
+                dy.max(0).min(h - 1) as u32
+            );
+            page.put_pixel((x as i64 + dx) as u32, (y as i64 + dy) as u32, src);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // a spread of sizes from a fixed seed, so failures reproduce
+    fn cells(count: usize, max: u32) -> Vec<(u32, u32)> {
+        let mut seed = 12345u32;
+        let mut next = move || {
+            seed = seed.wrapping_mul(1103515245).wrapping_add(12345);
+            (seed >> 16) % max + 1
+        };
+        (0..count).map(|_| (next(), next())).collect()
+    }
+
+    #[test]
+    fn placements_do_not_overlap() {
+        let cells = cells(200, 40);
+        let placements = pack(128, 128, &cells).unwrap();
+        for i in 0..cells.len() {
+            for j in i + 1..cells.len() {
+                let (a, b) = (placements[i], placements[j]);
+                if a.page != b.page {
+                    continue;
+                }
+                let apart = a.x + cells[i].0 <= b.x
+                    || b.x + cells[j].0 <= a.x
+                    || a.y + cells[i].1 <= b.y
+                    || b.y + cells[j].1 <= a.y;
+                assert!(apart, "cell {} {:?} overlaps cell {} {:?}", i, a, j, b);
+            }
+        }
+    }
+
+    #[test]
+    fn placements_stay_inside_the_page() {
+        let cells = cells(200, 40);
+        let placements = pack(128, 96, &cells).unwrap();
+        for (cell, placement) in cells.iter().zip(&placements) {
+            assert!(placement.x + cell.0 <= 128, "{:?} {:?}", cell, placement);
+            assert!(placement.y + cell.1 <= 96, "{:?} {:?}", cell, placement);
+        }
+        let pages = placements.iter().map(|p| p.page + 1).max().unwrap();
+        assert!(pages > 1);
+    }
+
+    #[test]
+    fn packing_is_deterministic() {
+        let cells = cells(100, 50);
+        assert_eq!(pack(256, 256, &cells), pack(256, 256, &cells));
+    }
+
+    #[test]
+    fn oversized_cells_are_rejected() {
+        assert_eq!(pack(64, 64, &[(10, 10), (65, 4), (8, 8)]), Err(1));
+        assert_eq!(pack(64, 64, &[(4, 65)]), Err(0));
+
+        let mut builder = AtlasBuilder::new(64, 64);
+        // 63 + 2 * padding doesn't fit
+        builder.add("small", &DynamicImage::new_rgba8(8, 8));
+        builder.add("large", &DynamicImage::new_rgba8(63, 8));
+        let error = builder.build().err().unwrap();
+        assert!(error.starts_with("large "), "{}", error);
+    }
+
+    #[test]
+    fn rects_exclude_padding() {
+        let mut builder = AtlasBuilder::new(64, 64);
+        builder.padding = 2;
+        builder.add("a", &DynamicImage::new_rgba8(10, 6));
+        let atlas = builder.build().unwrap();
+        let rect = atlas.rect("a").unwrap();
+        assert_eq!((rect.x, rect.y, rect.width, rect.height), (2, 2, 10, 6));
+        assert_eq!(rect.uv, [2.0 / 64.0, 2.0 / 64.0, 12.0 / 64.0, 8.0 / 64.0]);
+    }
+}
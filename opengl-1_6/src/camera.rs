@@ -0,0 +1,143 @@
+use cgmath::{Deg, Matrix4, Point3, Vector3, perspective, vec3};
+use cgmath::prelude::*;
+
+use crate::input::InputMap;
+
+const MAX_PITCH: f32 = 89.0;
+
+pub struct Camera {
+    pub position: Point3<f32>,
+    // degrees, yaw = -90 looks down -Z
+    pub yaw: f32,
+    pub pitch: f32,
+    pub fov: Deg<f32>,
+    pub near: f32,
+    pub far: f32
+}
+
+impl Camera {
+    pub fn new(position: Point3<f32>) -> Camera {
+        Camera {
+            position,
+            yaw: -90.0,
+            pitch: 0.0,
+            fov: Deg(45.0),
+            near: 0.1,
+            far: 100.0
+        }
+    }
+
+    pub fn front(&self) -> Vector3<f32> {
+        let (yaw, pitch) = (self.yaw.to_radians(), self.pitch.to_radians());
+        vec3(
+            yaw.cos() * pitch.cos(),
+            pitch.sin(),
+            yaw.sin() * pitch.cos()
+        ).normalize()
+    }
+
+    pub fn right(&self) -> Vector3<f32> {
+        self.front().cross(Vector3::unit_y()).normalize()
+    }
+
+    pub fn up(&self) -> Vector3<f32> {
+        self.right().cross(self.front()).normalize()
+    }
+
+    pub fn rotate(&mut self, yaw: f32, pitch: f32) {
+        self.yaw += yaw;
+        self.pitch = (self.pitch + pitch).clamp(-MAX_PITCH, MAX_PITCH);
+    }
+
+    pub fn view(&self) -> Matrix4<f32> {
+        Matrix4::look_at(self.position, self.position + self.front(), self.up())
+    }
+
+    pub fn projection(&self, aspect: f32) -> Matrix4<f32> {
+        perspective(self.fov, aspect, self.near, self.far)
+    }
+}
+
+pub trait CameraController {
+    fn update(&mut self, camera: &mut Camera, input: &InputMap, dt: f32);
+}
+
+// Looks around with the look_x/look_y axes (per frame deltas, e.g. the
+// cursor) and turn_x/turn_y (rates, e.g. a stick), moves with move_right,
+// move_forward and move_up, and zooms with the zoom axis.
+pub struct FlyController {
+    pub speed: f32,
+    pub sensitivity: f32,
+    // degrees per second at full turn_x/turn_y
+    pub turn_speed: f32
+}
+
+impl FlyController {
+    pub fn new() -> FlyController {
+        FlyController {
+            speed: 2.5,
+            sensitivity: 0.1,
+            turn_speed: 120.0
+        }
+    }
+}
+
+impl Default for FlyController {
+    fn default() -> FlyController {
+        FlyController::new()
+    }
+}
+
+impl CameraController for FlyController {
+    fn update(&mut self, camera: &mut Camera, input: &InputMap, dt: f32) {
+        camera.rotate(
+            input.axis("look_x") * self.sensitivity + input.axis("turn_x") * self.turn_speed * dt,
+            -input.axis("look_y") * self.sensitivity - input.axis("turn_y") * self.turn_speed * dt
+        );
+        camera.fov = Deg((camera.fov.0 - input.axis("zoom")).clamp(1.0, 45.0));
+
+        let step = self.speed * dt;
+        camera.position += camera.front() * input.axis("move_forward") * step
+            + camera.right() * input.axis("move_right") * step
+            + Vector3::unit_y() * input.axis("move_up") * step;
+    }
+}
+
+// Orbits around target while orbit_drag is held or with turn_x/turn_y,
+// zooms with the zoom axis.
+pub struct OrbitController {
+    pub target: Point3<f32>,
+    pub distance: f32,
+    pub sensitivity: f32,
+    pub turn_speed: f32,
+    pub zoom_speed: f32
+}
+
+impl OrbitController {
+    pub fn new(target: Point3<f32>, distance: f32) -> OrbitController {
+        OrbitController {
+            target,
+            distance,
+            sensitivity: 0.3,
+            turn_speed: 120.0,
+            zoom_speed: 0.1
+        }
+    }
+}
+
+impl CameraController for OrbitController {
+    fn update(&mut self, camera: &mut Camera, input: &InputMap, dt: f32) {
+        if input.held("orbit_drag") {
+            camera.rotate(
+                input.axis("look_x") * self.sensitivity,
+                -input.axis("look_y") * self.sensitivity
+            );
+        }
+        camera.rotate(
+            input.axis("turn_x") * self.turn_speed * dt,
+            -input.axis("turn_y") * self.turn_speed * dt
+        );
+        self.distance = (self.distance * (1.0 - input.axis("zoom") * self.zoom_speed)).max(camera.near);
+        camera.position = self.target - camera.front() * self.distance;
+    }
+}
@@ -0,0 +1,237 @@
+use std::{fs, path::Path};
+
+use glfw::{Action, GamepadAxis, GamepadButton, GamepadState, Glfw, JoystickId};
+
+use crate::input::InputMap;
+
+const AXIS_COUNT: usize = 6;
+const BUTTON_COUNT: usize = 15;
+
+// One frame of gamepad state in GLFW's layout, either polled or recorded.
+// Axes are LeftX, LeftY, RightX, RightY, LeftTrigger, RightTrigger; sticks
+// go from -1 to 1 with +Y pointing down, triggers rest at -1.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct GamepadSample {
+    pub axes: [f32; AXIS_COUNT],
+    pub buttons: [bool; BUTTON_COUNT]
+}
+
+impl GamepadSample {
+    // Sticks centered, triggers and buttons released.
+    pub fn released() -> GamepadSample {
+        GamepadSample {
+            axes: [0., 0., 0., 0., -1., -1.],
+            buttons: [false; BUTTON_COUNT]
+        }
+    }
+
+    pub fn from_state(state: &GamepadState) -> GamepadSample {
+        let mut sample = GamepadSample::released();
+        for (i, value) in sample.axes.iter_mut().enumerate() {
+            *value = state.get_axis(gamepad_axis(i));
+        }
+        for (i, down) in sample.buttons.iter_mut().enumerate() {
+            *down = state.get_button_state(gamepad_button(i)) == Action::Press;
+        }
+        sample
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum GamepadEvent {
+    Connected { id: JoystickId, name: String },
+    Disconnected(JoystickId)
+}
+
+// Polls GLFW joysticks that have a gamepad mapping and feeds the first
+// connected one into an InputMap, before its state is queried.
+pub struct Gamepads {
+    // radial, as a fraction of the stick range
+    pub dead_zone: f32,
+    pub trigger_dead_zone: f32,
+    connected: Vec<JoystickId>
+}
+
+impl Gamepads {
+    pub fn new() -> Gamepads {
+        Gamepads {
+            dead_zone: 0.2,
+            trigger_dead_zone: 0.1,
+            connected: Vec::new()
+        }
+    }
+
+    // Adds SDL_GameControllerDB style mappings on top of the ones GLFW ships with.
+    pub fn load_mappings<P: AsRef<Path>>(glfw: &Glfw, path: P) -> Result<(), String> {
+        let path = path.as_ref();
+        let mappings = fs::read_to_string(path)
+            .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+        if glfw.update_gamepad_mappings(&mappings) {
+            Ok(())
+        } else {
+            Err(format!("Invalid gamepad mappings in {}", path.display()))
+        }
+    }
+
+    // The gamepad driving the input map, the first one connected.
+    pub fn active(&self) -> Option<JoystickId> {
+        self.connected.first().cloned()
+    }
+
+    pub fn poll(&mut self, glfw: &Glfw, input: &mut InputMap) -> Vec<GamepadEvent> {
+        let mut events = Vec::new();
+        for id in (0..16).filter_map(JoystickId::from_i32) {
+            let joystick = glfw.get_joystick(id);
+            let present = joystick.is_present() && joystick.is_gamepad();
+            let known = self.connected.contains(&id);
+            if present && !known {
+                self.connected.push(id);
+                events.push(GamepadEvent::Connected {
+                    id,
+                    name: joystick.get_gamepad_name().unwrap_or_default()
+                });
+            } else if !present && known {
+                self.connected.retain(|&c| c != id);
+                events.push(GamepadEvent::Disconnected(id));
+            }
+        }
+
+        // with nothing connected this releases whatever was still held
+        let sample = self.active()
+            .and_then(|id| glfw.get_joystick(id).get_gamepad_state())
+            .map_or(GamepadSample::released(), |state| GamepadSample::from_state(&state));
+        self.apply(&sample, input);
+        events
+    }
+
+    // Applies the dead zones and forwards the sample, also used to replay
+    // recorded samples without a controller.
+    pub fn apply(&self, sample: &GamepadSample, input: &mut InputMap) {
+        let a = &sample.axes;
+        let (left_x, left_y) = radial_dead_zone(a[0], a[1], self.dead_zone);
+        let (right_x, right_y) = radial_dead_zone(a[2], a[3], self.dead_zone);
+        let values = [
+            left_x,
+            left_y,
+            right_x,
+            right_y,
+            trigger_dead_zone(a[4], self.trigger_dead_zone),
+            trigger_dead_zone(a[5], self.trigger_dead_zone)
+        ];
+        for (i, &value) in values.iter().enumerate() {
+            input.set_gamepad_axis(gamepad_axis(i), value);
+        }
+        for (i, &down) in sample.buttons.iter().enumerate() {
+            input.set_gamepad_button(gamepad_button(i), down);
+        }
+    }
+}
+
+impl Default for Gamepads {
+    fn default() -> Gamepads {
+        Gamepads::new()
+    }
+}
+
+// Zero inside the dead zone, then rescaled so the output still covers 0..1
+// along the stick's direction instead of jumping to dead_zone.
+pub fn radial_dead_zone(x: f32, y: f32, dead_zone: f32) -> (f32, f32) {
+    let length = (x * x + y * y).sqrt();
+    if length <= dead_zone {
+        return (0., 0.);
+    }
+    let scaled = ((length - dead_zone) / (1. - dead_zone)).min(1.);
+    (x / length * scaled, y / length * scaled)
+}
+
+// Maps a GLFW trigger from -1..1 to 0..1 with the dead zone at the rest end.
+pub fn trigger_dead_zone(value: f32, dead_zone: f32) -> f32 {
+    let value = (value + 1.) * 0.5;
+    if value <= dead_zone {
+        0.
+    } else {
+        ((value - dead_zone) / (1. - dead_zone)).min(1.)
+    }
+}
+
+fn gamepad_axis(i: usize) -> GamepadAxis {
+    GamepadAxis::from_i32(i as i32).expect("Gamepad axis index out of range")
+}
+
+fn gamepad_button(i: usize) -> GamepadButton {
+    GamepadButton::from_i32(i as i32).expect("Gamepad button index out of range")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::input::{AxisBinding, Binding, Input};
+
+    fn close(actual: (f32, f32), expected: (f32, f32)) -> bool {
+        (actual.0 - expected.0).abs() < 1e-5 && (actual.1 - expected.1).abs() < 1e-5
+    }
+
+    #[test]
+    fn radial_dead_zone_edges() {
+        // inside and on the boundary
+        assert_eq!(radial_dead_zone(0.1, -0.1, 0.2), (0., 0.));
+        assert_eq!(radial_dead_zone(0., 0.2, 0.2), (0., 0.));
+        assert_eq!(radial_dead_zone(-0.12, 0.16, 0.2), (0., 0.));
+        // just outside starts near zero instead of jumping to the dead zone
+        assert!(close(radial_dead_zone(0.28, 0., 0.2), (0.1, 0.)));
+        // halfway through the live range
+        assert!(close(radial_dead_zone(0., -0.6, 0.2), (0., -0.5)));
+        // full deflection
+        assert!(close(radial_dead_zone(1., 0., 0.2), (1., 0.)));
+        assert!(close(radial_dead_zone(0., -1., 0.2), (0., -1.)));
+    }
+
+    #[test]
+    fn radial_dead_zone_diagonals() {
+        let d = 0.5f32.sqrt();
+        // keeps the direction
+        assert!(close(radial_dead_zone(0.6 * d, 0.6 * d, 0.2), (0.5 * d, 0.5 * d)));
+        // square gates report corners past the unit circle, clamped to length 1
+        assert!(close(radial_dead_zone(1., 1., 0.2), (d, d)));
+        assert!(close(radial_dead_zone(-1., 1., 0.2), (-d, d)));
+        // a diagonal inside the zone although both axes alone are not
+        assert_eq!(radial_dead_zone(0.15, 0.15, 0.25), (0., 0.));
+    }
+
+    #[test]
+    fn trigger_dead_zone_edges() {
+        assert_eq!(trigger_dead_zone(-1., 0.1), 0.);
+        // 0.1 of the way down, on the boundary
+        assert_eq!(trigger_dead_zone(-0.8, 0.1), 0.);
+        assert!((trigger_dead_zone(0.0, 0.1) - 0.4 / 0.9).abs() < 1e-5);
+        assert_eq!(trigger_dead_zone(1., 0.1), 1.);
+    }
+
+    #[test]
+    fn recorded_samples_drive_the_input_map() {
+        let mut input = InputMap::new();
+        input.bind("jump", Binding::Input(Input::Gamepad(GamepadButton::ButtonA)));
+        input.bind_axis_scaled("move_forward", AxisBinding::Gamepad(GamepadAxis::AxisLeftY), -1.);
+        input.bind_axis("turn_x", AxisBinding::Gamepad(GamepadAxis::AxisRightX));
+        input.bind_axis("accelerate", AxisBinding::Gamepad(GamepadAxis::AxisRightTrigger));
+        let gamepads = Gamepads::new();
+
+        let mut sample = GamepadSample::released();
+        // resting drift stays in the dead zone
+        sample.axes[2] = 0.1;
+        sample.axes[1] = -1.;
+        sample.axes[5] = 1.;
+        sample.buttons[GamepadButton::ButtonA as usize] = true;
+        gamepads.apply(&sample, &mut input);
+        assert!((input.axis("move_forward") - 1.).abs() < 1e-5);
+        assert_eq!(input.axis("turn_x"), 0.);
+        assert_eq!(input.axis("accelerate"), 1.);
+        assert!(input.pressed("jump") && input.held("jump"));
+        input.end_frame();
+
+        gamepads.apply(&GamepadSample::released(), &mut input);
+        assert_eq!(input.axis("move_forward"), 0.);
+        assert_eq!(input.axis("accelerate"), 0.);
+        assert!(input.released("jump") && !input.held("jump"));
+    }
+}
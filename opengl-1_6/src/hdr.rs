@@ -0,0 +1,162 @@
+use std::{ffi::c_void, fs::File, io::BufReader, path::Path};
+
+use common::gl_check;
+use gl::types::GLenum;
+use image::hdr::HDRDecoder;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum HdrPrecision {
+    Half,
+    Full
+}
+
+pub struct HdrImage {
+    pub width: u32,
+    pub height: u32,
+    // 3 for Radiance files, 4 for EXR
+    pub channels: u32,
+    pub data: Vec<f32>
+}
+
+impl HdrImage {
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<HdrImage, String> {
+        let path = path.as_ref();
+        match path.extension().and_then(|e| e.to_str()) {
+            Some("hdr") | Some("pic") => decode_radiance(path),
+            #[cfg(feature = "exr")]
+            Some("exr") => decode_exr(path),
+            _ => Err(format!("{} is not a supported HDR image", path.display()))
+        }
+    }
+
+    pub fn flipv(&mut self) {
+        let row = (self.width * self.channels) as usize;
+        let height = self.height as usize;
+        for y in 0..height / 2 {
+            let (top, bottom) = self.data.split_at_mut((height - 1 - y) * row);
+            top[y * row..(y + 1) * row].swap_with_slice(&mut bottom[..row]);
+        }
+    }
+
+    pub fn internal_format(&self, precision: HdrPrecision) -> GLenum {
+        match (self.channels, precision) {
+            (4, HdrPrecision::Half) => gl::RGBA16F,
+            (4, HdrPrecision::Full) => gl::RGBA32F,
+            (_, HdrPrecision::Half) => gl::RGB16F,
+            (_, HdrPrecision::Full) => gl::RGB32F
+        }
+    }
+
+    /// # Safety
+    ///
+    /// Needs a current GL context.
+    pub unsafe fn upload(&self, precision: HdrPrecision) -> Result<u32, String> {
+        if self.width == 0 || self.height == 0 {
+            return Err(format!("Cannot upload an empty {}x{} HDR image", self.width, self.height));
+        }
+        let format = if self.channels == 4 { gl::RGBA } else { gl::RGB };
+
+        let mut id = 0;
+        gl_check!(gl::GenTextures(1, &mut id));
+        gl_check!(gl::BindTexture(gl::TEXTURE_2D, id));
+        // wrapping
+        gl_check!(gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_WRAP_S, gl::CLAMP_TO_EDGE as i32));
+        gl_check!(gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_WRAP_T, gl::CLAMP_TO_EDGE as i32));
+        // filtering
+        gl_check!(gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_MIN_FILTER, gl::LINEAR as i32));
+        gl_check!(gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_MAG_FILTER, gl::LINEAR as i32));
+        gl_check!(gl::TexImage2D(
+            gl::TEXTURE_2D,
+            0,
+            self.internal_format(precision) as i32,
+            self.width as i32,
+            self.height as i32,
+            0,
+            format,
+            gl::FLOAT,
+            self.data.as_ptr() as *const c_void
+        ));
+        Ok(id)
+    }
+}
+
+fn decode_radiance(path: &Path) -> Result<HdrImage, String> {
+    let file = File::open(path).map_err(|e| format!("Failed to open {}: {}", path.display(), e))?;
+    let decoder = HDRDecoder::new(BufReader::new(file)).map_err(|e| e.to_string())?;
+    let meta = decoder.metadata();
+    // RGBE is expanded to float by the decoder
+    let pixels = decoder.read_image_hdr().map_err(|e| e.to_string())?;
+
+    let mut data = Vec::with_capacity(pixels.len() * 3);
+    for pixel in pixels {
+        data.extend_from_slice(&pixel.data);
+    }
+    Ok(HdrImage { width: meta.width, height: meta.height, channels: 3, data })
+}
+
+#[cfg(feature = "exr")]
+fn decode_exr(path: &Path) -> Result<HdrImage, String> {
+    use exr::prelude::{read_first_rgba_layer_from_file, Vec2};
+
+    let image = read_first_rgba_layer_from_file(
+        path,
+        |size: Vec2<usize>, _| HdrImage {
+            width: size.width() as u32,
+            height: size.height() as u32,
+            channels: 4,
+            data: vec![0.0; size.area() * 4]
+        },
+        |img: &mut HdrImage, pos: Vec2<usize>, (r, g, b, a): (f32, f32, f32, f32)| {
+            let i = (pos.y() * img.width as usize + pos.x()) * 4;
+            img.data[i..i + 4].copy_from_slice(&[r, g, b, a]);
+        }
+    ).map_err(|e| e.to_string())?;
+    Ok(image.layer_data.channel_data.pixels)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use super::*;
+
+    fn fixture(name: &str) -> PathBuf {
+        Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures").join(name)
+    }
+
+    #[test]
+    fn decodes_radiance() {
+        let image = HdrImage::open(fixture("small.hdr")).unwrap();
+        assert_eq!((image.width, image.height, image.channels), (3, 2, 3));
+        assert_eq!(image.data, vec![
+            1.0, 0.5, 0.25, 2.0, 2.0, 2.0, 0.0, 0.0, 0.0,
+            0.5, 0.0, 0.0, 0.0, 8.0, 0.0, 0.0, 0.0, 0.375
+        ]);
+        assert_eq!(image.internal_format(HdrPrecision::Half), gl::RGB16F);
+    }
+
+    #[test]
+    fn flipv_swaps_rows() {
+        let mut image = HdrImage::open(fixture("small.hdr")).unwrap();
+        image.flipv();
+        assert_eq!(&image.data[..9], &[0.5, 0.0, 0.0, 0.0, 8.0, 0.0, 0.0, 0.0, 0.375]);
+        assert_eq!(&image.data[9..], &[1.0, 0.5, 0.25, 2.0, 2.0, 2.0, 0.0, 0.0, 0.0]);
+    }
+
+    #[test]
+    fn rejects_other_formats() {
+        assert!(HdrImage::open(fixture("small.png")).is_err());
+    }
+
+    #[cfg(feature = "exr")]
+    #[test]
+    fn decodes_exr() {
+        let image = HdrImage::open(fixture("small.exr")).unwrap();
+        assert_eq!((image.width, image.height, image.channels), (2, 2, 4));
+        assert_eq!(image.data, vec![
+            1.0, 0.5, 0.25, 1.0, 4.0, 0.0, 0.0, 0.5,
+            0.0, 16.0, 0.0, 1.0, 0.125, 0.125, 2.5, 0.0
+        ]);
+        assert_eq!(image.internal_format(HdrPrecision::Full), gl::RGBA32F);
+    }
+}
@@ -0,0 +1,475 @@
+use std::{
+    collections::{HashMap, HashSet},
+    fs,
+    path::Path
+};
+
+use glfw::{Action, GamepadAxis, GamepadButton, Key, MouseButton, WindowEvent};
+use serde::Deserialize;
+
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum Input {
+    Key(Key),
+    Mouse(MouseButton),
+    Gamepad(GamepadButton)
+}
+
+// What can trigger a named action
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Binding {
+    Input(Input),
+    ScrollUp,
+    ScrollDown
+}
+
+// What can drive a named axis
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum AxisBinding {
+    // +1 while positive is held, -1 while negative is held
+    Inputs { positive: Input, negative: Input },
+    ScrollX,
+    ScrollY,
+    // cursor movement since the last frame, in screen coordinates
+    CursorX,
+    CursorY,
+    Gamepad(GamepadAxis)
+}
+
+#[derive(Deserialize)]
+struct Config {
+    #[serde(default)]
+    actions: HashMap<String, Vec<String>>,
+    #[serde(default)]
+    axes: HashMap<String, Vec<AxisConfig>>
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum AxisConfig {
+    Source(String),
+    Inputs { positive: String, negative: String },
+    Scaled { source: String, scale: f32 }
+}
+
+// Maps raw GLFW events to named actions and axes. Feed it the frame's
+// events, query the state, then call end_frame() so the next frame's
+// presses, scrolling and cursor movement start from nothing.
+#[derive(Default)]
+pub struct InputMap {
+    actions: HashMap<String, Vec<Binding>>,
+    // each binding is multiplied by its scale before summing
+    axes: HashMap<String, Vec<(AxisBinding, f32)>>,
+    down: HashSet<Input>,
+    pressed: HashSet<Input>,
+    released: HashSet<Input>,
+    scroll: (f64, f64),
+    cursor: Option<(f64, f64)>,
+    cursor_delta: (f64, f64),
+    gamepad_axes: HashMap<GamepadAxis, f32>
+}
+
+impl InputMap {
+    pub fn new() -> InputMap {
+        InputMap::default()
+    }
+
+    pub fn from_file<P: AsRef<Path>>(path: P) -> Result<InputMap, String> {
+        let path = path.as_ref();
+        let src = fs::read_to_string(path)
+            .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+        InputMap::from_toml(&src).map_err(|e| format!("{}: {}", path.display(), e))
+    }
+
+    pub fn from_toml(src: &str) -> Result<InputMap, String> {
+        let config: Config = toml::from_str(src).map_err(|e| e.to_string())?;
+        let mut map = InputMap::new();
+        for (action, names) in &config.actions {
+            for name in names {
+                map.bind(action, parse_binding(name).ok_or_else(|| format!("unknown input {:?}", name))?);
+            }
+        }
+        for (axis, sources) in &config.axes {
+            for source in sources {
+                let (binding, scale) = match source {
+                    AxisConfig::Source(name) => (parse_axis(name), 1.),
+                    AxisConfig::Inputs { positive, negative } => (parse_input(positive)
+                        .and_then(|p| parse_input(negative).map(|n| AxisBinding::Inputs { positive: p, negative: n })), 1.),
+                    AxisConfig::Scaled { source, scale } => (parse_axis(source), *scale)
+                };
+                map.bind_axis_scaled(axis, binding.ok_or_else(|| format!("unknown axis source in {:?}", axis))?, scale);
+            }
+        }
+        Ok(map)
+    }
+
+    pub fn bind(&mut self, action: &str, binding: Binding) {
+        self.actions.entry(action.to_string()).or_default().push(binding);
+    }
+
+    // Replaces every binding of the action, e.g. from a settings screen.
+    pub fn rebind(&mut self, action: &str, binding: Binding) {
+        self.actions.insert(action.to_string(), vec![binding]);
+    }
+
+    pub fn bind_axis(&mut self, axis: &str, binding: AxisBinding) {
+        self.bind_axis_scaled(axis, binding, 1.);
+    }
+
+    // e.g. a scale of -1 flips the stick Y axis, which GLFW reports as
+    // negative when pushed up
+    pub fn bind_axis_scaled(&mut self, axis: &str, binding: AxisBinding, scale: f32) {
+        self.axes.entry(axis.to_string()).or_default().push((binding, scale));
+    }
+
+    pub fn end_frame(&mut self) {
+        self.pressed.clear();
+        self.released.clear();
+        self.scroll = (0., 0.);
+        self.cursor_delta = (0., 0.);
+    }
+
+    pub fn handle_event(&mut self, event: &WindowEvent) {
+        match *event {
+            WindowEvent::Key(key, _, Action::Press, _) => self.press(Input::Key(key)),
+            WindowEvent::Key(key, _, Action::Release, _) => self.release(Input::Key(key)),
+            WindowEvent::MouseButton(button, Action::Press, _) => self.press(Input::Mouse(button)),
+            WindowEvent::MouseButton(button, Action::Release, _) => self.release(Input::Mouse(button)),
+            WindowEvent::Scroll(x, y) => {
+                self.scroll.0 += x;
+                self.scroll.1 += y;
+            }
+            WindowEvent::CursorPos(x, y) => {
+                if let Some((last_x, last_y)) = self.cursor {
+                    self.cursor_delta.0 += x - last_x;
+                    self.cursor_delta.1 += y - last_y;
+                }
+                self.cursor = Some((x, y));
+            }
+            _ => {}
+        }
+    }
+
+    pub fn set_gamepad_button(&mut self, button: GamepadButton, down: bool) {
+        let input = Input::Gamepad(button);
+        match (down, self.down.contains(&input)) {
+            (true, false) => self.press(input),
+            (false, true) => self.release(input),
+            _ => {}
+        }
+    }
+
+    pub fn set_gamepad_axis(&mut self, axis: GamepadAxis, value: f32) {
+        self.gamepad_axes.insert(axis, value);
+    }
+
+    // Forget the last cursor position, e.g. after the cursor mode changed,
+    // so the next CursorPos does not produce a jump.
+    pub fn reset_cursor(&mut self) {
+        self.cursor = None;
+    }
+
+    pub fn cursor(&self) -> Option<(f64, f64)> {
+        self.cursor
+    }
+
+    pub fn pressed(&self, action: &str) -> bool {
+        self.any(action, |map, binding| match binding {
+            Binding::Input(input) => map.pressed.contains(&input),
+            Binding::ScrollUp => map.scroll.1 > 0.,
+            Binding::ScrollDown => map.scroll.1 < 0.
+        })
+    }
+
+    pub fn held(&self, action: &str) -> bool {
+        self.any(action, |map, binding| match binding {
+            Binding::Input(input) => map.down.contains(&input),
+            Binding::ScrollUp => map.scroll.1 > 0.,
+            Binding::ScrollDown => map.scroll.1 < 0.
+        })
+    }
+
+    pub fn released(&self, action: &str) -> bool {
+        self.any(action, |map, binding| match binding {
+            Binding::Input(input) => map.released.contains(&input),
+            Binding::ScrollUp | Binding::ScrollDown => false
+        })
+    }
+
+    // Sum of every scaled binding of the axis, 0 for unknown axes.
+    pub fn axis(&self, axis: &str) -> f32 {
+        let bindings = match self.axes.get(axis) {
+            Some(bindings) => bindings,
+            None => return 0.
+        };
+        bindings.iter().map(|&(binding, scale)| scale * match binding {
+            AxisBinding::Inputs { positive, negative } => {
+                self.down.contains(&positive) as i32 as f32 - self.down.contains(&negative) as i32 as f32
+            }
+            AxisBinding::ScrollX => self.scroll.0 as f32,
+            AxisBinding::ScrollY => self.scroll.1 as f32,
+            AxisBinding::CursorX => self.cursor_delta.0 as f32,
+            AxisBinding::CursorY => self.cursor_delta.1 as f32,
+            AxisBinding::Gamepad(axis) => self.gamepad_axes.get(&axis).cloned().unwrap_or(0.)
+        }).sum()
+    }
+
+    fn any<F: Fn(&InputMap, Binding) -> bool>(&self, action: &str, f: F) -> bool {
+        self.actions
+            .get(action)
+            .is_some_and(|bindings| bindings.iter().any(|&b| f(self, b)))
+    }
+
+    fn press(&mut self, input: Input) {
+        // key repeat does not count as another press
+        if self.down.insert(input) {
+            self.pressed.insert(input);
+        }
+    }
+
+    fn release(&mut self, input: Input) {
+        if self.down.remove(&input) {
+            self.released.insert(input);
+        }
+    }
+}
+
+fn parse_binding(name: &str) -> Option<Binding> {
+    match name {
+        "ScrollUp" => Some(Binding::ScrollUp),
+        "ScrollDown" => Some(Binding::ScrollDown),
+        _ => parse_input(name).map(Binding::Input)
+    }
+}
+
+fn parse_axis(name: &str) -> Option<AxisBinding> {
+    Some(match name {
+        "ScrollX" => AxisBinding::ScrollX,
+        "ScrollY" => AxisBinding::ScrollY,
+        "CursorX" => AxisBinding::CursorX,
+        "CursorY" => AxisBinding::CursorY,
+        "GamepadLeftX" => AxisBinding::Gamepad(GamepadAxis::AxisLeftX),
+        "GamepadLeftY" => AxisBinding::Gamepad(GamepadAxis::AxisLeftY),
+        "GamepadRightX" => AxisBinding::Gamepad(GamepadAxis::AxisRightX),
+        "GamepadRightY" => AxisBinding::Gamepad(GamepadAxis::AxisRightY),
+        "GamepadLeftTrigger" => AxisBinding::Gamepad(GamepadAxis::AxisLeftTrigger),
+        "GamepadRightTrigger" => AxisBinding::Gamepad(GamepadAxis::AxisRightTrigger),
+        _ => return None
+    })
+}
+
+macro_rules! keys {
+    ($name:expr, $($key:ident),*) => {
+        match $name {
+            $(stringify!($key) => Some(Input::Key(Key::$key)),)*
+            _ => None
+        }
+    }
+}
+
+// Names follow the glfw enums: "W", "Escape", "LeftShift", "Mouse1",
+// "GamepadA", "GamepadDpadUp"...
+pub fn parse_input(name: &str) -> Option<Input> {
+    let gamepad = match name {
+        "GamepadA" => Some(GamepadButton::ButtonA),
+        "GamepadB" => Some(GamepadButton::ButtonB),
+        "GamepadX" => Some(GamepadButton::ButtonX),
+        "GamepadY" => Some(GamepadButton::ButtonY),
+        "GamepadLeftBumper" => Some(GamepadButton::ButtonLeftBumper),
+        "GamepadRightBumper" => Some(GamepadButton::ButtonRightBumper),
+        "GamepadBack" => Some(GamepadButton::ButtonBack),
+        "GamepadStart" => Some(GamepadButton::ButtonStart),
+        "GamepadGuide" => Some(GamepadButton::ButtonGuide),
+        "GamepadLeftThumb" => Some(GamepadButton::ButtonLeftThumb),
+        "GamepadRightThumb" => Some(GamepadButton::ButtonRightThumb),
+        "GamepadDpadUp" => Some(GamepadButton::ButtonDpadUp),
+        "GamepadDpadRight" => Some(GamepadButton::ButtonDpadRight),
+        "GamepadDpadDown" => Some(GamepadButton::ButtonDpadDown),
+        "GamepadDpadLeft" => Some(GamepadButton::ButtonDpadLeft),
+        _ => None
+    };
+    if let Some(button) = gamepad {
+        return Some(Input::Gamepad(button));
+    }
+
+    let mouse = match name {
+        "Mouse1" | "MouseLeft" => Some(MouseButton::Button1),
+        "Mouse2" | "MouseRight" => Some(MouseButton::Button2),
+        "Mouse3" | "MouseMiddle" => Some(MouseButton::Button3),
+        "Mouse4" => Some(MouseButton::Button4),
+        "Mouse5" => Some(MouseButton::Button5),
+        _ => None
+    };
+    if let Some(button) = mouse {
+        return Some(Input::Mouse(button));
+    }
+
+    keys!(name,
+        A, B, C, D, E, F, G, H, I, J, K, L, M, N, O, P, Q, R, S, T, U, V, W, X, Y, Z,
+        Num0, Num1, Num2, Num3, Num4, Num5, Num6, Num7, Num8, Num9,
+        F1, F2, F3, F4, F5, F6, F7, F8, F9, F10, F11, F12,
+        Space, Escape, Enter, Tab, Backspace, Insert, Delete,
+        Right, Left, Down, Up, PageUp, PageDown, Home, End,
+        LeftShift, LeftControl, LeftAlt, RightShift, RightControl, RightAlt,
+        Minus, Equal, Comma, Period, Slash, Semicolon, Apostrophe, GraveAccent,
+        LeftBracket, RightBracket, Backslash,
+        Kp0, Kp1, Kp2, Kp3, Kp4, Kp5, Kp6, Kp7, Kp8, Kp9, KpAdd, KpSubtract
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use glfw::Modifiers;
+
+    use super::*;
+
+    fn key(key: Key, action: Action) -> WindowEvent {
+        WindowEvent::Key(key, 0, action, Modifiers::empty())
+    }
+
+    fn state(map: &InputMap, action: &str) -> (bool, bool, bool) {
+        (map.pressed(action), map.held(action), map.released(action))
+    }
+
+    #[test]
+    fn press_held_release() {
+        let mut map = InputMap::new();
+        map.bind("jump", Binding::Input(Input::Key(Key::Space)));
+        assert_eq!(state(&map, "jump"), (false, false, false));
+
+        map.handle_event(&key(Key::Space, Action::Press));
+        assert_eq!(state(&map, "jump"), (true, true, false));
+        map.end_frame();
+        assert_eq!(state(&map, "jump"), (false, true, false));
+
+        // key repeat is not another press
+        map.handle_event(&key(Key::Space, Action::Repeat));
+        map.handle_event(&key(Key::Space, Action::Press));
+        assert_eq!(state(&map, "jump"), (false, true, false));
+        map.end_frame();
+
+        map.handle_event(&key(Key::Space, Action::Release));
+        assert_eq!(state(&map, "jump"), (false, false, true));
+        map.end_frame();
+        assert_eq!(state(&map, "jump"), (false, false, false));
+
+        // a release without a press is ignored
+        map.handle_event(&key(Key::Space, Action::Release));
+        assert_eq!(state(&map, "jump"), (false, false, false));
+    }
+
+    #[test]
+    fn tap_within_one_frame() {
+        let mut map = InputMap::new();
+        map.bind("fire", Binding::Input(Input::Mouse(MouseButton::Button1)));
+        map.handle_event(&WindowEvent::MouseButton(MouseButton::Button1, Action::Press, Modifiers::empty()));
+        map.handle_event(&WindowEvent::MouseButton(MouseButton::Button1, Action::Release, Modifiers::empty()));
+        assert_eq!(state(&map, "fire"), (true, false, true));
+    }
+
+    #[test]
+    fn any_binding_triggers_the_action() {
+        let mut map = InputMap::new();
+        map.bind("zoom_in", Binding::Input(Input::Key(Key::Equal)));
+        map.bind("zoom_in", Binding::ScrollUp);
+        map.handle_event(&WindowEvent::Scroll(0., 1.));
+        assert!(map.pressed("zoom_in") && map.held("zoom_in"));
+        map.end_frame();
+        assert!(!map.held("zoom_in"));
+        assert!(!map.pressed("unbound"));
+    }
+
+    #[test]
+    fn rebinding() {
+        let mut map = InputMap::new();
+        map.bind("jump", Binding::Input(Input::Key(Key::Space)));
+        map.handle_event(&key(Key::Space, Action::Press));
+        map.handle_event(&key(Key::J, Action::Press));
+        map.end_frame();
+
+        map.rebind("jump", Binding::Input(Input::Key(Key::J)));
+        // held state follows the input, not the action
+        assert_eq!(state(&map, "jump"), (false, true, false));
+        map.handle_event(&key(Key::J, Action::Release));
+        map.handle_event(&key(Key::Space, Action::Release));
+        assert_eq!(state(&map, "jump"), (false, false, true));
+        map.end_frame();
+
+        map.handle_event(&key(Key::Space, Action::Press));
+        assert_eq!(state(&map, "jump"), (false, false, false));
+        map.handle_event(&key(Key::J, Action::Press));
+        assert_eq!(state(&map, "jump"), (true, true, false));
+    }
+
+    #[test]
+    fn axes() {
+        let mut map = InputMap::new();
+        map.bind_axis("move_right", AxisBinding::Inputs { positive: Input::Key(Key::D), negative: Input::Key(Key::A) });
+        map.bind_axis("look_x", AxisBinding::CursorX);
+        map.bind_axis_scaled("look_y", AxisBinding::CursorY, -0.5);
+
+        map.handle_event(&key(Key::D, Action::Press));
+        assert_eq!(map.axis("move_right"), 1.);
+        map.handle_event(&key(Key::A, Action::Press));
+        assert_eq!(map.axis("move_right"), 0.);
+        map.handle_event(&key(Key::D, Action::Release));
+        assert_eq!(map.axis("move_right"), -1.);
+
+        // the first position only sets the reference
+        map.handle_event(&WindowEvent::CursorPos(100., 100.));
+        map.handle_event(&WindowEvent::CursorPos(110., 96.));
+        map.handle_event(&WindowEvent::CursorPos(115., 90.));
+        assert_eq!((map.axis("look_x"), map.axis("look_y")), (15., 5.));
+        map.end_frame();
+        assert_eq!(map.axis("look_x"), 0.);
+
+        map.reset_cursor();
+        map.handle_event(&WindowEvent::CursorPos(0., 0.));
+        assert_eq!(map.axis("look_x"), 0.);
+        assert_eq!(map.axis("unknown"), 0.);
+    }
+
+    #[test]
+    fn gamepad_buttons_and_axes() {
+        let mut map = InputMap::new();
+        map.bind("jump", Binding::Input(Input::Gamepad(GamepadButton::ButtonA)));
+        map.bind_axis_scaled("move_forward", AxisBinding::Gamepad(GamepadAxis::AxisLeftY), -1.);
+
+        map.set_gamepad_button(GamepadButton::ButtonA, true);
+        assert_eq!(state(&map, "jump"), (true, true, false));
+        map.end_frame();
+        // polled every frame, only changes count
+        map.set_gamepad_button(GamepadButton::ButtonA, true);
+        assert_eq!(state(&map, "jump"), (false, true, false));
+        map.set_gamepad_button(GamepadButton::ButtonA, false);
+        assert_eq!(state(&map, "jump"), (false, false, true));
+
+        map.set_gamepad_axis(GamepadAxis::AxisLeftY, -0.75);
+        assert_eq!(map.axis("move_forward"), 0.75);
+    }
+
+    #[test]
+    fn from_toml() {
+        let map = InputMap::from_toml(r#"
+            [actions]
+            quit = ["Escape", "GamepadBack"]
+            zoom = ["ScrollUp"]
+
+            [axes]
+            move_forward = [{ positive = "W", negative = "S" }, { source = "GamepadLeftY", scale = -1.0 }]
+            look_x = ["CursorX"]
+        "#).unwrap();
+        assert_eq!(map.actions["quit"], vec![
+            Binding::Input(Input::Key(Key::Escape)),
+            Binding::Input(Input::Gamepad(GamepadButton::ButtonBack))
+        ]);
+        assert_eq!(map.actions["zoom"], vec![Binding::ScrollUp]);
+        assert_eq!(map.axes["move_forward"], vec![
+            (AxisBinding::Inputs { positive: Input::Key(Key::W), negative: Input::Key(Key::S) }, 1.),
+            (AxisBinding::Gamepad(GamepadAxis::AxisLeftY), -1.)
+        ]);
+        assert_eq!(map.axes["look_x"], vec![(AxisBinding::CursorX, 1.)]);
+
+        assert!(InputMap::from_toml("[actions]\nquit = [\"NoSuchKey\"]").is_err());
+        assert!(InputMap::from_toml("[axes]\nlook = [\"CursorZ\"]").is_err());
+    }
+}
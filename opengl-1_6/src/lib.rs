@@ -0,0 +1,401 @@
+#![allow(non_upper_case_globals)]
+use cgmath::{Deg, Matrix4, Point3, Quaternion, vec3};
+use cgmath::prelude::*;
+use gl::{self, types::{GLfloat, GLsizei, GLsizeiptr}};
+use std::{ffi::{c_void}, mem, path::Path, ptr};
+use common::{app::{App, Chapter, Context}, egui, profile, ui::RenderState};
+use common::culling::{self, Aabb, CullStats, Frustum};
+use common::scene::{Material, Mesh, Scene, Transform};
+
+pub mod atlas;
+pub mod camera;
+pub mod gamepad;
+pub mod hdr;
+pub mod input;
+pub mod picking;
+pub mod projection;
+pub mod shader;
+pub mod texture;
+use camera::{Camera, CameraController, FlyController, OrbitController};
+use gamepad::{GamepadEvent, Gamepads};
+use input::InputMap;
+use picking::{IdBuffer, Ray};
+use projection::{Projection, Viewport};
+use shader::Shader;
+use texture::{ColorSpace, TextureLoader};
+
+// path, flipv
+const TEXTURE_FILES: [(&str, bool); 2] = [
+    ("assets/textures/container.jpg", false),
+    ("assets/textures/awesomeface.png", true)
+];
+
+pub struct CoordinateSystems {
+    vertices: Vec<f32>,
+    shader: Shader,
+    textures: TextureLoader,
+    texture: (u32, u32),
+    // G toggles between gamma-correct rendering and the old gamma-space blending
+    srgb: bool,
+    scene: Scene,
+    // C switches between flying around and orbiting the point in front of the camera
+    camera: Camera,
+    fly: FlyController,
+    orbit: OrbitController,
+    orbiting: bool,
+    viewport: Viewport,
+    // right click picks the cube under the cursor, or under the crosshair while flying
+    id_buffer: IdBuffer,
+    pick_request: Option<(f64, f64)>,
+    visible: Vec<usize>,
+    cull_stats: CullStats,
+    input: InputMap,
+    gamepads: Gamepads,
+    percent: f32,
+    render_state: RenderState
+}
+
+pub fn chapter() -> Chapter {
+    Chapter::new::<CoordinateSystems>("1.6", env!("CARGO_MANIFEST_DIR"))
+}
+
+impl App for CoordinateSystems {
+    fn init(ctx: &mut Context) -> CoordinateSystems {
+        ctx.set_cursor_mode(glfw::CursorMode::Disabled);
+
+        let vertices = vec![
+            // coords          // tex coords 
+            -0.5, -0.5, -0.5,  0.0, 0.0,
+             0.5, -0.5, -0.5,  1.0, 0.0,
+             0.5,  0.5, -0.5,  1.0, 1.0,
+             0.5,  0.5, -0.5,  1.0, 1.0,
+            -0.5,  0.5, -0.5,  0.0, 1.0,
+            -0.5, -0.5, -0.5,  0.0, 0.0,
+    
+            -0.5, -0.5,  0.5,  0.0, 0.0,
+             0.5, -0.5,  0.5,  1.0, 0.0,
+             0.5,  0.5,  0.5,  1.0, 1.0,
+             0.5,  0.5,  0.5,  1.0, 1.0,
+            -0.5,  0.5,  0.5,  0.0, 1.0,
+            -0.5, -0.5,  0.5,  0.0, 0.0,
+    
+            -0.5,  0.5,  0.5,  1.0, 0.0,
+            -0.5,  0.5, -0.5,  1.0, 1.0,
+            -0.5, -0.5, -0.5,  0.0, 1.0,
+            -0.5, -0.5, -0.5,  0.0, 1.0,
+            -0.5, -0.5,  0.5,  0.0, 0.0,
+            -0.5,  0.5,  0.5,  1.0, 0.0,
+    
+             0.5,  0.5,  0.5,  1.0, 0.0,
+             0.5,  0.5, -0.5,  1.0, 1.0,
+             0.5, -0.5, -0.5,  0.0, 1.0,
+             0.5, -0.5, -0.5,  0.0, 1.0,
+             0.5, -0.5,  0.5,  0.0, 0.0,
+             0.5,  0.5,  0.5,  1.0, 0.0,
+    
+            -0.5, -0.5, -0.5,  0.0, 1.0,
+             0.5, -0.5, -0.5,  1.0, 1.0,
+             0.5, -0.5,  0.5,  1.0, 0.0,
+             0.5, -0.5,  0.5,  1.0, 0.0,
+            -0.5, -0.5,  0.5,  0.0, 0.0,
+            -0.5, -0.5, -0.5,  0.0, 1.0,
+    
+            -0.5,  0.5, -0.5,  0.0, 1.0,
+             0.5,  0.5, -0.5,  1.0, 1.0,
+             0.5,  0.5,  0.5,  1.0, 0.0,
+             0.5,  0.5,  0.5,  1.0, 0.0,
+            -0.5,  0.5,  0.5,  0.0, 0.0,
+            -0.5,  0.5, -0.5,  0.0, 1.0
+        ];
+
+        let indices = vec![
+            0, 1, 3,
+            1, 2, 3,
+        ];
+
+        let cube_positions = vec![
+            vec3( 0.0,  0.0,  0.0), 
+            vec3( 2.0,  5.0, -15.0), 
+            vec3(-1.5, -2.2, -2.5),  
+            vec3(-3.8, -2.0, -12.3),  
+            vec3( 2.4, -0.4, -3.5),  
+            vec3(-1.7,  3.0, -7.5),  
+            vec3( 1.3, -2.0, -2.5),  
+            vec3( 1.5,  2.0, -2.5), 
+            vec3( 1.5,  0.2, -1.5), 
+            vec3(-1.3,  1.0, -1.5)  
+        ];
+
+        let srgb = true;
+        let mut textures = TextureLoader::new(2, 4 * 1024 * 1024);
+        let (shader, vao, texture) = {
+            let shader = Shader::new(
+                "shaders/vertex.glsl",
+                "shaders/fragment.glsl"
+            );
+
+            let vao = build_vaos(&vertices, &indices);
+
+            let texture = (
+                textures.load(TEXTURE_FILES[0].0, TEXTURE_FILES[0].1, color_space(srgb)),
+                textures.load(TEXTURE_FILES[1].0, TEXTURE_FILES[1].1, color_space(srgb))
+            );
+
+            unsafe {
+                shader.use_program();
+                shader.set_int("texture1", 0);
+                shader.set_int("texture2", 1);
+            }
+
+            unsafe {
+                // gl::PolygonMode(gl::FRONT_AND_BACK, gl::LINE);
+                gl::Enable(gl::DEPTH_TEST);
+                gl::Enable(gl::FRAMEBUFFER_SRGB);
+            }
+            (shader, vao, texture)
+        };
+
+        let cube_bounds = Aabb::from_vertices(&vertices, 5);
+        let mut scene = Scene::new();
+        for (i, pos) in cube_positions.iter().enumerate() {
+            let cube = scene.add(&format!("cube{}", i), Transform {
+                rotation: Quaternion::from_axis_angle(vec3(1.0, 0.3, 0.5).normalize(), Deg(20. * i as f32)),
+                ..Transform::from_translation(*pos)
+            }, None);
+            let node = scene.node_mut(cube);
+            node.mesh = Some(Mesh { vao, vertex_count: 36, bounds: Some(cube_bounds) });
+            node.material = Some(Material { textures: vec![texture.0, texture.1] });
+        }
+        scene.update_world();
+
+        let (fb_width, fb_height) = ctx.framebuffer_size();
+
+        let gamepads = Gamepads::new();
+        match ctx.glfw() {
+            Some(glfw) if Path::new("gamecontrollerdb.txt").exists() => {
+                if let Err(e) = Gamepads::load_mappings(glfw, "gamecontrollerdb.txt") {
+                    println!("ERROR::GAMEPAD::MAPPINGS {}", e);
+                }
+            }
+            _ => {}
+        }
+
+        CoordinateSystems {
+            visible: Vec::with_capacity(scene.len()),
+            vertices,
+            shader,
+            textures,
+            texture,
+            srgb,
+            scene,
+            camera: Camera::new(Point3::new(0., 0., 4.)),
+            fly: FlyController::new(),
+            orbit: OrbitController::new(Point3::new(0., 0., 0.), 4.),
+            orbiting: false,
+            viewport: Viewport::new(fb_width, fb_height, Projection::Perspective),
+            id_buffer: IdBuffer::new(fb_width, fb_height),
+            pick_request: None,
+            cull_stats: CullStats::default(),
+            input: InputMap::from_file("input.toml").expect("Failed to load input bindings"),
+            gamepads,
+            percent: 0.2,
+            render_state: RenderState::new([0.2, 0.3, 0.3, 1.0], true)
+        }
+    }
+
+    fn on_event(&mut self, _ctx: &mut Context, event: &glfw::WindowEvent) {
+        self.input.handle_event(event);
+        if let glfw::WindowEvent::FramebufferSize(w, h) = *event {
+            self.viewport.resize(w, h);
+            self.id_buffer.resize(w, h);
+        }
+    }
+
+    fn update(&mut self, ctx: &mut Context, dt: f32) {
+        if let Some(glfw) = ctx.glfw() {
+            for event in self.gamepads.poll(glfw, &mut self.input) {
+                match event {
+                    GamepadEvent::Connected { id, name } => log::info!("gamepad {:?} connected: {}", id, name),
+                    GamepadEvent::Disconnected(id) => log::info!("gamepad {:?} disconnected", id)
+                }
+            }
+        }
+
+        if self.input.pressed("quit") {
+            ctx.quit();
+        }
+        if self.input.pressed("mix_up") {
+            self.percent = self.percent.clamp(0.0, 0.9) + 0.1;
+        }
+        if self.input.pressed("mix_down") {
+            self.percent = self.percent.clamp(0.1, 1.0) - 0.1;
+        }
+        if self.input.pressed("cycle_projection") {
+            self.viewport.set_projection(self.viewport.projection().next());
+        }
+        if self.input.pressed("toggle_orbit") {
+            self.orbiting = !self.orbiting;
+            if self.orbiting {
+                self.orbit = OrbitController::new(self.camera.position + self.camera.front() * 4., 4.);
+                ctx.set_cursor_mode(glfw::CursorMode::Normal);
+            } else {
+                ctx.set_cursor_mode(glfw::CursorMode::Disabled);
+            }
+            self.input.reset_cursor();
+        }
+        if self.input.pressed("pick") {
+            self.pick_request = Some(if self.orbiting {
+                ctx.cursor_pos()
+            } else {
+                let (w, h) = ctx.size();
+                (w as f64 / 2., h as f64 / 2.)
+            });
+        }
+        if self.input.pressed("toggle_srgb") {
+            self.srgb = !self.srgb;
+            unsafe {
+                if self.srgb {
+                    gl::Enable(gl::FRAMEBUFFER_SRGB);
+                } else {
+                    gl::Disable(gl::FRAMEBUFFER_SRGB);
+                }
+            }
+            let space = color_space(self.srgb);
+            self.textures.reload(self.texture.0, TEXTURE_FILES[0].0, TEXTURE_FILES[0].1, space);
+            self.textures.reload(self.texture.1, TEXTURE_FILES[1].0, TEXTURE_FILES[1].1, space);
+        }
+
+        let controller: &mut dyn CameraController = if self.orbiting { &mut self.orbit } else { &mut self.fly };
+        controller.update(&mut self.camera, &self.input, dt);
+        self.viewport.follow(&self.camera);
+
+        // everything above has seen this frame's events, the next ones
+        // arrive before the next update
+        self.input.end_frame();
+        if ctx.is_headless() {
+            // a checkerboard in one run and the texture in the next would
+            // make headless frames differ
+            self.textures.finish();
+        } else {
+            self.textures.update();
+        }
+    }
+
+    fn ui(&mut self, _ctx: &mut Context, ui: &egui::Context) {
+        egui::Window::new("1.6").show(ui, |ui| {
+            ui.add(egui::Slider::new(&mut self.percent, 0.0..=1.0).text("percent"));
+            self.render_state.ui(ui);
+            ui.label(format!("{} visible, {} culled", self.cull_stats.visible, self.cull_stats.culled));
+            cubes_ui(ui, &mut self.scene);
+        });
+    }
+
+    fn fixed_update(&mut self, ctx: &mut Context, step: f32) {
+        self.scene.update((ctx.time + step as f64) as f32, step);
+    }
+
+    fn render(&mut self, ctx: &mut Context, _alpha: f32) {
+        let view: Matrix4<f32> = self.camera.view();
+        let frustum = Frustum::from_matrix(&(self.viewport.matrix() * view));
+        self.cull_stats = profile!("cull", { culling::cull(&self.scene, &frustum, &mut self.visible) });
+
+        if let Some(cursor) = self.pick_request.take() {
+            let window_size = ctx.size();
+            let ray = Ray::from_cursor(cursor, window_size, &self.viewport, &view);
+            let by_ray = picking::pick_triangles(&self.scene, &ray, &self.vertices, 5);
+            let by_id = profile!("pick", {
+                unsafe { self.id_buffer.pick(&self.scene, self.visible.iter().cloned(), &self.viewport, &view, cursor, window_size) }
+            });
+            println!("picked: ray {:?}, id buffer {:?}",
+                by_ray.map(|(id, _)| &self.scene.node(id).name),
+                by_id.map(|id| &self.scene.node(id).name)
+            );
+        }
+
+        unsafe {
+            self.render_state.apply();
+            gl::Clear(gl::COLOR_BUFFER_BIT | gl::DEPTH_BUFFER_BIT);
+
+            // transformations
+            self.shader.use_program();
+            self.shader.set_mat4f("view",  &view);
+            self.viewport.apply(&self.shader);
+            
+            self.shader.set_float("percent", self.percent);
+            let shader = &self.shader;
+            profile!("draw cubes", {
+                self.scene.draw_nodes(self.visible.iter().cloned(), |_, model| shader.set_mat4f("model", model))
+            });
+            // gl::DrawElements(gl::TRIANGLES, 6, gl::UNSIGNED_INT, ptr::null());      
+        }
+    }
+}
+
+// A collapsible transform editor per cube, picked up by the next update.
+fn cubes_ui(ui: &mut egui::Ui, scene: &mut Scene) {
+    ui.collapsing("cubes", |ui| {
+        for id in 0..scene.len() {
+            let node = scene.node(id);
+            let mut local = *node.local();
+            let edited = ui.collapsing(node.name.as_str(), |ui| local.ui(ui)).body_returned == Some(true);
+            if edited {
+                *scene.transform_mut(id) = local;
+            }
+        }
+    });
+}
+
+fn color_space(srgb: bool) -> ColorSpace {
+    if srgb { ColorSpace::Srgb } else { ColorSpace::Linear }
+}
+
+fn build_vaos(vertices: &Vec<f32>, indices: &Vec<i32>) -> u32 {
+    unsafe {
+        let (mut vbo, mut vao, mut ebo) = (0, 0, 0);
+        gl::GenVertexArrays(1, &mut vao);
+        gl::GenBuffers(1, &mut vbo);
+        gl::GenBuffers(1, &mut ebo);
+
+        // first triangle
+        gl::BindVertexArray(vao);
+        gl::BindBuffer(gl::ARRAY_BUFFER, vbo);
+        gl::BufferData(
+            gl::ARRAY_BUFFER, 
+            (vertices.len() * mem::size_of::<GLfloat>()) as GLsizeiptr, 
+            &vertices[0] as *const f32 as *const c_void, 
+            gl::STATIC_DRAW
+        );
+        gl::BindBuffer(gl::ELEMENT_ARRAY_BUFFER, ebo);
+        gl::BufferData(
+            gl::ELEMENT_ARRAY_BUFFER, 
+            (indices.len() * mem::size_of::<GLfloat>()) as GLsizeiptr, 
+            &indices[0] as *const i32 as *const c_void, 
+            gl::STATIC_DRAW
+        );
+
+        let stride = 5 * mem::size_of::<GLfloat>() as GLsizei;
+        // aPos
+        gl::VertexAttribPointer(
+            0,
+            3,
+            gl::FLOAT,
+            gl::FALSE,
+            stride,
+            ptr::null()
+        );
+        gl::EnableVertexAttribArray(0);
+        
+        // aTexCoord
+        gl::VertexAttribPointer(
+            1,
+            2,
+            gl::FLOAT,
+            gl::FALSE,
+            stride,
+            (3 * mem::size_of::<GLfloat>()) as *const c_void
+        );
+        gl::EnableVertexAttribArray(1);
+
+        vao
+    }
+}
\ No newline at end of file
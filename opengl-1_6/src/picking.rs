@@ -0,0 +1,254 @@
+use std::{ffi::c_void, ptr};
+
+use cgmath::{Matrix4, Point3, Vector3, vec4};
+use cgmath::prelude::*;
+use common::{culling::Aabb, debug, gl_check, scene::{NodeId, Scene}};
+
+use crate::projection::{Projection, Viewport};
+use crate::shader::Shader;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Ray {
+    pub origin: Point3<f32>,
+    pub direction: Vector3<f32>
+}
+
+impl Ray {
+    // `cursor` and `window_size` are in screen coordinates, as reported by
+    // CursorPos events, which differ from framebuffer pixels on HiDPI.
+    pub fn from_cursor(cursor: (f64, f64), window_size: (i32, i32), viewport: &Viewport, view: &Matrix4<f32>) -> Ray {
+        let x = (2. * cursor.0 / window_size.0.max(1) as f64 - 1.) as f32;
+        let y = (1. - 2. * cursor.1 / window_size.1.max(1) as f64) as f32;
+        // NDC depth of two points in front of the camera, the reversed
+        // infinite projection puts the far plane at 0 where w is 0 too
+        let (near, far) = match viewport.projection() {
+            Projection::ReversedInfinite => (1., 0.5),
+            _ => (-1., 1.)
+        };
+
+        let inverse = (viewport.matrix() * view).invert().expect("Singular proj * view matrix");
+        let unproject = |z: f32| {
+            let p = inverse * vec4(x, y, z, 1.);
+            Point3::new(p.x / p.w, p.y / p.w, p.z / p.w)
+        };
+        let (origin, target) = (unproject(near), unproject(far));
+        Ray { origin, direction: (target - origin).normalize() }
+    }
+
+    pub fn transform(&self, m: &Matrix4<f32>) -> Ray {
+        // direction is left unnormalized so distances stay comparable
+        Ray { origin: m.transform_point(self.origin), direction: m.transform_vector(self.direction) }
+    }
+
+    pub fn at(&self, t: f32) -> Point3<f32> {
+        self.origin + self.direction * t
+    }
+
+    // Slab test, returns the distance to the entry point or 0 when the origin
+    // is inside the box.
+    pub fn intersect_aabb(&self, aabb: &Aabb) -> Option<f32> {
+        let (mut t_min, mut t_max) = (0f32, f32::INFINITY);
+        for axis in 0..3 {
+            let (o, d) = (self.origin[axis], self.direction[axis]);
+            let (lo, hi) = (aabb.min[axis], aabb.max[axis]);
+            if d.abs() < f32::EPSILON {
+                if o < lo || o > hi {
+                    return None;
+                }
+                continue;
+            }
+            let (t0, t1) = ((lo - o) / d, (hi - o) / d);
+            t_min = t_min.max(t0.min(t1));
+            t_max = t_max.min(t0.max(t1));
+            if t_min > t_max {
+                return None;
+            }
+        }
+        Some(t_min)
+    }
+
+    // Moller-Trumbore, both faces count as hits.
+    pub fn intersect_triangle(&self, a: Point3<f32>, b: Point3<f32>, c: Point3<f32>) -> Option<f32> {
+        let (e1, e2) = (b - a, c - a);
+        let p = self.direction.cross(e2);
+        let det = e1.dot(p);
+        if det.abs() < f32::EPSILON {
+            return None;
+        }
+        let inv_det = 1. / det;
+        let s = self.origin - a;
+        let u = s.dot(p) * inv_det;
+        if !(0. ..=1.).contains(&u) {
+            return None;
+        }
+        let q = s.cross(e1);
+        let v = self.direction.dot(q) * inv_det;
+        if v < 0. || u + v > 1. {
+            return None;
+        }
+        let t = e2.dot(q) * inv_det;
+        if t >= 0. { Some(t) } else { None }
+    }
+
+    // Non-indexed triangle list, `stride` is in floats with the position first.
+    pub fn intersect_triangles(&self, vertices: &[f32], stride: usize) -> Option<f32> {
+        let point = |i: usize| Point3::new(vertices[i * stride], vertices[i * stride + 1], vertices[i * stride + 2]);
+        (0..vertices.len() / stride / 3)
+            .filter_map(|t| self.intersect_triangle(point(3 * t), point(3 * t + 1), point(3 * t + 2)))
+            .fold(None, |best: Option<f32>, t| Some(best.map_or(t, |b| b.min(t))))
+    }
+}
+
+// Closest node whose bounds the ray hits, with the distance along the ray.
+pub fn pick_bounds(scene: &Scene, ray: &Ray) -> Option<(NodeId, f32)> {
+    pick(scene, ray, |local, bounds| local.intersect_aabb(bounds))
+}
+
+// Like pick_bounds, but nodes hit by their bounds are then tested against
+// the triangles in `vertices`, which all meshes are assumed to share.
+pub fn pick_triangles(scene: &Scene, ray: &Ray, vertices: &[f32], stride: usize) -> Option<(NodeId, f32)> {
+    pick(scene, ray, |local, bounds| {
+        local.intersect_aabb(bounds)?;
+        local.intersect_triangles(vertices, stride)
+    })
+}
+
+fn pick<F: Fn(&Ray, &Aabb) -> Option<f32>>(scene: &Scene, ray: &Ray, hit: F) -> Option<(NodeId, f32)> {
+    let mut best: Option<(NodeId, f32)> = None;
+    for (id, node) in scene.nodes() {
+        let bounds = match node.mesh.and_then(|m| m.bounds) {
+            Some(bounds) => bounds,
+            None => continue
+        };
+        let inverse = match node.world().invert() {
+            Some(inverse) => inverse,
+            None => continue
+        };
+        // testing in local space keeps the box tight under rotation, and t
+        // is preserved by the affine transform
+        let local = ray.transform(&inverse);
+        if let Some(t) = hit(&local, &bounds) {
+            let closer = match best {
+                Some((_, best_t)) => t < best_t,
+                None => true
+            };
+            if closer {
+                best = Some((id, t));
+            }
+        }
+    }
+    best
+}
+
+// Renders node ids + 1 into an R32UI attachment, 0 means nothing was hit.
+pub struct IdBuffer {
+    pub width: i32,
+    pub height: i32,
+    fbo: u32,
+    color: u32,
+    depth: u32,
+    shader: Shader
+}
+
+impl IdBuffer {
+    pub fn new(width: i32, height: i32) -> IdBuffer {
+        let mut buffer = IdBuffer {
+            width: 0,
+            height: 0,
+            fbo: 0,
+            color: 0,
+            depth: 0,
+            shader: Shader::new(
+                "shaders/id_vertex.glsl",
+                "shaders/id_fragment.glsl"
+            )
+        };
+        unsafe {
+            gl::GenFramebuffers(1, &mut buffer.fbo);
+            gl::GenTextures(1, &mut buffer.color);
+            gl::GenRenderbuffers(1, &mut buffer.depth);
+        }
+        buffer.resize(width, height);
+        // objects only exist to be named once resize() has bound them
+        debug::label(gl::FRAMEBUFFER, buffer.fbo, "id buffer");
+        debug::label(gl::TEXTURE, buffer.color, "id buffer ids");
+        debug::label(gl::RENDERBUFFER, buffer.depth, "id buffer depth");
+        buffer
+    }
+
+    pub fn resize(&mut self, width: i32, height: i32) {
+        self.width = width.max(1);
+        self.height = height.max(1);
+        unsafe {
+            gl_check!(gl::BindTexture(gl::TEXTURE_2D, self.color));
+            gl_check!(gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_MIN_FILTER, gl::NEAREST as i32));
+            gl_check!(gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_MAG_FILTER, gl::NEAREST as i32));
+            gl_check!(gl::TexImage2D(
+                gl::TEXTURE_2D,
+                0,
+                gl::R32UI as i32,
+                self.width,
+                self.height,
+                0,
+                gl::RED_INTEGER,
+                gl::UNSIGNED_INT,
+                ptr::null()
+            ));
+
+            gl_check!(gl::BindRenderbuffer(gl::RENDERBUFFER, self.depth));
+            gl_check!(gl::RenderbufferStorage(gl::RENDERBUFFER, gl::DEPTH_COMPONENT24, self.width, self.height));
+
+            gl_check!(gl::BindFramebuffer(gl::FRAMEBUFFER, self.fbo));
+            gl_check!(gl::FramebufferTexture2D(gl::FRAMEBUFFER, gl::COLOR_ATTACHMENT0, gl::TEXTURE_2D, self.color, 0));
+            gl_check!(gl::FramebufferRenderbuffer(gl::FRAMEBUFFER, gl::DEPTH_ATTACHMENT, gl::RENDERBUFFER, self.depth));
+            if gl::CheckFramebufferStatus(gl::FRAMEBUFFER) != gl::FRAMEBUFFER_COMPLETE {
+                println!("ERROR::FRAMEBUFFER:: id buffer is not complete");
+            }
+            gl_check!(gl::BindFramebuffer(gl::FRAMEBUFFER, 0));
+        }
+    }
+
+    /// Draws `ids` and reads back the one pixel under the cursor. `cursor`
+    /// and `window_size` are in screen coordinates.
+    ///
+    /// # Safety
+    ///
+    /// Needs a current GL context in which this buffer was created and
+    /// resized to the viewport, and the VAOs of the scene's meshes are still
+    /// alive. Binds the ID framebuffer and reads from it, then leaves the
+    /// default framebuffer bound and the viewport reset to `viewport`.
+    pub unsafe fn pick<I: IntoIterator<Item = NodeId>>(
+        &self,
+        scene: &Scene,
+        ids: I,
+        viewport: &Viewport,
+        view: &Matrix4<f32>,
+        cursor: (f64, f64),
+        window_size: (i32, i32)
+    ) -> Option<NodeId> {
+        gl::BindFramebuffer(gl::FRAMEBUFFER, self.fbo);
+        gl::Viewport(0, 0, self.width, self.height);
+        gl::ClearBufferuiv(gl::COLOR, 0, [0u32; 4].as_ptr());
+        gl::ClearBufferfv(gl::DEPTH, 0, &(viewport.clear_depth() as f32));
+
+        self.shader.use_program();
+        self.shader.set_mat4f("view", view);
+        self.shader.set_mat4f("proj", &viewport.matrix());
+        scene.draw_nodes(ids, |id, model| {
+            self.shader.set_uint("objectId", id as u32 + 1);
+            self.shader.set_mat4f("model", model);
+        });
+
+        let x = (cursor.0 / window_size.0.max(1) as f64 * self.width as f64) as i32;
+        let y = self.height - 1 - (cursor.1 / window_size.1.max(1) as f64 * self.height as f64) as i32;
+        let mut picked = 0u32;
+        if x >= 0 && x < self.width && y >= 0 && y < self.height {
+            gl::ReadBuffer(gl::COLOR_ATTACHMENT0);
+            gl::ReadPixels(x, y, 1, 1, gl::RED_INTEGER, gl::UNSIGNED_INT, &mut picked as *mut u32 as *mut c_void);
+        }
+
+        gl::BindFramebuffer(gl::FRAMEBUFFER, 0);
+        gl::Viewport(0, 0, viewport.width, viewport.height);
+        if picked == 0 { None } else { Some(picked as NodeId - 1) }
+    }
+}
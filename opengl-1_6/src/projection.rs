@@ -0,0 +1,137 @@
+use cgmath::{Deg, Matrix4, Rad, ortho, perspective, vec2};
+use cgmath::prelude::*;
+
+use crate::camera::Camera;
+use crate::shader::Shader;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Projection {
+    Perspective,
+    // height of the visible area in world units
+    Orthographic { height: f32 },
+    // far plane at infinity, depth 1 at the near plane and 0 at infinity
+    ReversedInfinite
+}
+
+impl Projection {
+    pub fn matrix(self, fov: Deg<f32>, near: f32, far: f32, aspect: f32) -> Matrix4<f32> {
+        match self {
+            Projection::Perspective => perspective(fov, aspect, near, far),
+            Projection::Orthographic { height } => {
+                let (half_w, half_h) = (height * aspect / 2., height / 2.);
+                ortho(-half_w, half_w, -half_h, half_h, near, far)
+            }
+            Projection::ReversedInfinite => {
+                let f = 1. / (Rad::from(fov) / 2.).tan();
+                Matrix4::new(
+                    f / aspect, 0., 0.,   0.,
+                    0.,         f,  0.,   0.,
+                    0.,         0., 0.,  -1.,
+                    0.,         0., near, 0.
+                )
+            }
+        }
+    }
+
+    pub fn next(self) -> Projection {
+        match self {
+            Projection::Perspective => Projection::Orthographic { height: 8. },
+            Projection::Orthographic { .. } => Projection::ReversedInfinite,
+            Projection::ReversedInfinite => Projection::Perspective
+        }
+    }
+}
+
+// Owns the framebuffer size and the projection built from it. The matrix is
+// only rebuilt when the size, the projection or the camera lens change.
+pub struct Viewport {
+    pub width: i32,
+    pub height: i32,
+    projection: Projection,
+    lens: (Deg<f32>, f32, f32),
+    matrix: Matrix4<f32>
+}
+
+impl Viewport {
+    pub fn new(width: i32, height: i32, projection: Projection) -> Viewport {
+        let mut viewport = Viewport {
+            width,
+            height,
+            projection,
+            lens: (Deg(45.), 0.1, 100.),
+            matrix: Matrix4::identity()
+        };
+        viewport.rebuild();
+        unsafe { viewport.apply_depth_state() };
+        viewport
+    }
+
+    pub fn aspect(&self) -> f32 {
+        self.width as f32 / self.height.max(1) as f32
+    }
+
+    pub fn projection(&self) -> Projection {
+        self.projection
+    }
+
+    pub fn matrix(&self) -> Matrix4<f32> {
+        self.matrix
+    }
+
+    pub fn resize(&mut self, width: i32, height: i32) {
+        self.width = width;
+        self.height = height;
+        unsafe {
+            gl::Viewport(0, 0, width, height);
+        }
+        self.rebuild();
+    }
+
+    pub fn set_projection(&mut self, projection: Projection) {
+        self.projection = projection;
+        unsafe { self.apply_depth_state() };
+        self.rebuild();
+    }
+
+    pub fn follow(&mut self, camera: &Camera) {
+        let lens = (camera.fov, camera.near, camera.far);
+        if lens != self.lens {
+            self.lens = lens;
+            self.rebuild();
+        }
+    }
+
+    /// Sets `proj` and `viewportSize` (in pixels).
+    ///
+    /// # Safety
+    ///
+    /// Needs a current GL context with `shader` in use.
+    pub unsafe fn apply(&self, shader: &Shader) {
+        shader.set_mat4f("proj", &self.matrix);
+        shader.set_vec2f("viewportSize", vec2(self.width as f32, self.height as f32));
+    }
+
+    pub fn clear_depth(&self) -> f64 {
+        if self.projection == Projection::ReversedInfinite { 0.0 } else { 1.0 }
+    }
+
+    // Reversed-Z only pays off with [0, 1] clip space, which needs
+    // ARB_clip_control. Without it the matrix still works, just with
+    // regular precision.
+    unsafe fn apply_depth_state(&self) {
+        let reversed = self.projection == Projection::ReversedInfinite;
+        if gl::ClipControl::is_loaded() {
+            gl::ClipControl(
+                gl::LOWER_LEFT,
+                if reversed { gl::ZERO_TO_ONE } else { gl::NEGATIVE_ONE_TO_ONE }
+            );
+        }
+        gl::DepthFunc(if reversed { gl::GREATER } else { gl::LESS });
+        gl::ClearDepth(self.clear_depth());
+    }
+
+    fn rebuild(&mut self) {
+        let (fov, near, far) = self.lens;
+        self.matrix = self.projection.matrix(fov, near, far, self.aspect());
+    }
+}
@@ -1,6 +1,7 @@
 use std::{ffi::CString, fs::File, io::Read, ptr};
 
-use cgmath::{Array, Matrix, Matrix4};
+use cgmath::{Array, Matrix, Matrix4, Vector2};
+use common::{debug, uniforms};
 use gl::types::{GLchar, GLint};
 
 pub struct Shader {
@@ -54,6 +55,8 @@ impl Shader {
             
             shader.id = id;
         };
+        debug::label(gl::PROGRAM, shader.id, &format!("{} + {}", vertex_path, fragment_path));
+        uniforms::register(shader.id, vertex_path, fragment_path);
         
         shader
     }
@@ -67,6 +70,7 @@ impl Shader {
             gl::GetUniformLocation(self.id, self.str2cstr(name).as_ptr()),
             val as i32
         );
+        uniforms::reapply(self.id, name);
     }
 
     pub unsafe fn set_int(&self, name: &str, val: i32) {
@@ -74,6 +78,15 @@ impl Shader {
             gl::GetUniformLocation(self.id, self.str2cstr(name).as_ptr()),
             val
         );
+        uniforms::reapply(self.id, name);
+    }
+
+    pub unsafe fn set_uint(&self, name: &str, val: u32) {
+        gl::Uniform1ui(
+            gl::GetUniformLocation(self.id, self.str2cstr(name).as_ptr()),
+            val
+        );
+        uniforms::reapply(self.id, name);
     }
 
     pub unsafe fn set_float(&self, name: &str, val: f32) {
@@ -81,6 +94,16 @@ impl Shader {
             gl::GetUniformLocation(self.id, self.str2cstr(name).as_ptr()),
             val
         );
+        uniforms::reapply(self.id, name);
+    }
+
+    pub unsafe fn set_vec2f(&self, name: &str, val: Vector2<f32>) {
+        gl::Uniform2f(
+            gl::GetUniformLocation(self.id, self.str2cstr(name).as_ptr()),
+            val.x,
+            val.y
+        );
+        uniforms::reapply(self.id, name);
     }
 
     pub unsafe fn set_mat4f(&self, name: &str, val: &Matrix4<f32>){
@@ -89,6 +112,7 @@ impl Shader {
         gl::FALSE,
         val.as_ptr()
         );
+        uniforms::reapply(self.id, name);
     }
     
     unsafe fn str2cstr(&self, v: &str) -> CString {
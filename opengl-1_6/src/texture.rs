@@ -0,0 +1,243 @@
+use std::{
+    collections::VecDeque,
+    ffi::c_void,
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex, mpsc::{self, Receiver, Sender}},
+    thread::{self, JoinHandle}
+};
+
+use common::{debug, gl_check};
+use gl::types::GLenum;
+use image::{DynamicImage, GenericImage};
+
+const PLACEHOLDER_SIZE: usize = 64;
+const PLACEHOLDER_CELL: usize = 8;
+
+// Color maps (albedo, sprites) are authored in sRGB and get decoded to linear
+// by the sampler. Data maps (normals, roughness, masks) must stay Linear.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ColorSpace {
+    Srgb,
+    Linear
+}
+
+impl ColorSpace {
+    pub fn internal_format(self, format: GLenum) -> GLenum {
+        match (self, format) {
+            (ColorSpace::Srgb, gl::RGB) => gl::SRGB8,
+            (ColorSpace::Srgb, gl::RGBA) => gl::SRGB8_ALPHA8,
+            (ColorSpace::Linear, gl::RGB) => gl::RGB8,
+            (ColorSpace::Linear, gl::RGBA) => gl::RGBA8,
+            (_, format) => format
+        }
+    }
+}
+
+struct Job {
+    id: u32,
+    path: PathBuf,
+    flipv: bool,
+    space: ColorSpace
+}
+
+struct Decoded {
+    id: u32,
+    path: PathBuf,
+    space: ColorSpace,
+    pixels: Result<Pixels, String>
+}
+
+struct Pixels {
+    width: i32,
+    height: i32,
+    format: GLenum,
+    data: Vec<u8>
+}
+
+pub struct TextureLoader {
+    jobs: Option<Sender<Job>>,
+    results: Receiver<Decoded>,
+    workers: Vec<JoinHandle<()>>,
+    ready: VecDeque<Decoded>,
+    // requested and not uploaded yet
+    pending: usize,
+    // max bytes uploaded per update(), at least one texture is always uploaded
+    pub upload_budget: usize
+}
+
+impl TextureLoader {
+    pub fn new(workers: usize, upload_budget: usize) -> TextureLoader {
+        let (job_tx, job_rx) = mpsc::channel::<Job>();
+        let (result_tx, result_rx) = mpsc::channel::<Decoded>();
+        let job_rx = Arc::new(Mutex::new(job_rx));
+
+        let workers = (0..workers.max(1))
+            .map(|i| {
+                let job_rx = Arc::clone(&job_rx);
+                let result_tx = result_tx.clone();
+                thread::Builder::new()
+                    .name(format!("texture-decoder-{}", i))
+                    .spawn(move || decode_worker(job_rx, result_tx))
+                    .expect("Failed to spawn texture decoder thread")
+            })
+            .collect();
+
+        TextureLoader {
+            jobs: Some(job_tx),
+            results: result_rx,
+            workers,
+            ready: VecDeque::new(),
+            pending: 0,
+            upload_budget
+        }
+    }
+
+    // Returns a texture that is usable right away. It holds a checkerboard
+    // until the decoded image is uploaded by update().
+    pub fn load<P: AsRef<Path>>(&mut self, path: P, flipv: bool, space: ColorSpace) -> u32 {
+        let id = unsafe { placeholder_texture(space) };
+        debug::label(gl::TEXTURE, id, &path.as_ref().display().to_string());
+        self.reload(id, path, flipv, space);
+        id
+    }
+
+    // Decodes the image again and replaces the contents of an existing
+    // texture, keeping whatever it holds now until the upload.
+    pub fn reload<P: AsRef<Path>>(&mut self, id: u32, path: P, flipv: bool, space: ColorSpace) {
+        self.jobs
+            .as_ref()
+            .unwrap()
+            .send(Job { id, path: path.as_ref().to_path_buf(), flipv, space })
+            .expect("Texture decoder threads are gone");
+        self.pending += 1;
+    }
+
+    // Must be called on the thread that owns the GL context, once per frame.
+    pub fn update(&mut self) {
+        self.ready.extend(self.results.try_iter());
+
+        let mut uploaded = 0;
+        while let Some(decoded) = self.ready.front() {
+            let size = decoded.pixels.as_ref().map_or(0, |p| p.data.len());
+            if uploaded > 0 && uploaded + size > self.upload_budget {
+                break;
+            }
+            let decoded = self.ready.pop_front().unwrap();
+            uploaded += size;
+            self.upload_decoded(decoded);
+        }
+    }
+
+    // Waits for everything requested so far and uploads it regardless of
+    // the budget, for renders that must never show a placeholder.
+    pub fn finish(&mut self) {
+        while self.ready.len() < self.pending {
+            match self.results.recv() {
+                Ok(decoded) => self.ready.push_back(decoded),
+                Err(_) => break
+            }
+        }
+        while let Some(decoded) = self.ready.pop_front() {
+            self.upload_decoded(decoded);
+        }
+    }
+
+    fn upload_decoded(&mut self, decoded: Decoded) {
+        self.pending -= 1;
+        match decoded.pixels {
+            Ok(p) => unsafe { upload(decoded.id, p.width, p.height, decoded.space, p.format, &p.data) },
+            Err(err) => println!("ERROR::TEXTURE_LOADING_ERROR of {}\n{}\n",
+                decoded.path.display(),
+                err
+            )
+        }
+    }
+}
+
+impl Drop for TextureLoader {
+    fn drop(&mut self) {
+        // closing the job channel lets the workers fall out of their loop
+        self.jobs.take();
+        for worker in self.workers.drain(..) {
+            let _ = worker.join();
+        }
+    }
+}
+
+fn decode_worker(jobs: Arc<Mutex<Receiver<Job>>>, results: Sender<Decoded>) {
+    loop {
+        let job = match jobs.lock().unwrap().recv() {
+            Ok(job) => job,
+            Err(_) => return
+        };
+        let pixels = decode(&job.path, job.flipv);
+        if results.send(Decoded { id: job.id, path: job.path, space: job.space, pixels }).is_err() {
+            return;
+        }
+    }
+}
+
+fn decode(path: &Path, flipv: bool) -> Result<Pixels, String> {
+    let img = image::open(path).map_err(|e| e.to_string())?;
+    let img = if flipv { img.flipv() } else { img };
+    let (width, height) = (img.width() as i32, img.height() as i32);
+    // nothing to upload, the texture keeps its placeholder
+    if width == 0 || height == 0 {
+        return Err(format!("Cannot upload an empty {}x{} image", width, height));
+    }
+    let (format, data) = match img {
+        DynamicImage::ImageRgb8(_) => (gl::RGB, img.raw_pixels()),
+        DynamicImage::ImageRgba8(_) => (gl::RGBA, img.raw_pixels()),
+        _ => (gl::RGBA, img.to_rgba().into_raw())
+    };
+    Ok(Pixels { width, height, format, data })
+}
+
+unsafe fn placeholder_texture(space: ColorSpace) -> u32 {
+    let mut data = Vec::with_capacity(PLACEHOLDER_SIZE * PLACEHOLDER_SIZE * 3);
+    for y in 0..PLACEHOLDER_SIZE {
+        for x in 0..PLACEHOLDER_SIZE {
+            let odd = (x / PLACEHOLDER_CELL + y / PLACEHOLDER_CELL) % 2 == 1;
+            data.extend_from_slice(if odd { &[255, 0, 255] } else { &[0, 0, 0] });
+        }
+    }
+
+    create(PLACEHOLDER_SIZE as i32, PLACEHOLDER_SIZE as i32, space, gl::RGB, &data)
+}
+
+/// # Safety
+///
+/// Needs a current GL context. `data` has to hold `width` x `height` pixels
+/// of `format`.
+pub unsafe fn create(width: i32, height: i32, space: ColorSpace, format: GLenum, data: &[u8]) -> u32 {
+    let mut id = 0;
+    gl_check!(gl::GenTextures(1, &mut id));
+    gl_check!(gl::BindTexture(gl::TEXTURE_2D, id));
+    // wrapping
+    gl_check!(gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_WRAP_S, gl::REPEAT as i32));
+    gl_check!(gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_WRAP_T, gl::REPEAT as i32));
+    // filtering
+    gl_check!(gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_MIN_FILTER, gl::LINEAR as i32));
+    gl_check!(gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_MAG_FILTER, gl::LINEAR as i32));
+    upload(id, width, height, space, format, data);
+    id
+}
+
+unsafe fn upload(id: u32, width: i32, height: i32, space: ColorSpace, format: GLenum, data: &[u8]) {
+    gl_check!(gl::BindTexture(gl::TEXTURE_2D, id));
+    // rows of RGB images are not necessarily 4-byte aligned
+    gl_check!(gl::PixelStorei(gl::UNPACK_ALIGNMENT, 1));
+    gl_check!(gl::TexImage2D(
+        gl::TEXTURE_2D,
+        0,
+        space.internal_format(format) as i32,
+        width,
+        height,
+        0,
+        format,
+        gl::UNSIGNED_BYTE,
+        data.as_ptr() as *const c_void
+    ));
+    gl_check!(gl::PixelStorei(gl::UNPACK_ALIGNMENT, 4));
+    gl_check!(gl::GenerateMipmap(gl::TEXTURE_2D));
+}